@@ -0,0 +1,348 @@
+use scraper::{ElementRef, Html, Selector};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use super::tables;
+use super::text::{collapse_whitespace, BLOCK_ELEMENTS, HIDDEN_ELEMENTS};
+
+// Elements nested deeper than this are read as inline text, so deeply
+// nested markup cannot overflow the stack
+const MAX_DEPTH: usize = 64;
+
+// Representation of a page's content in `content`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContentFormat {
+    #[default]
+    Text,     // Plain text (html2text, 120 columns)
+    Markdown, // Markdown with headings, lists, links, tables and images
+    Blocks,   // Plain text in `content` plus the block tree in `blocks`
+}
+
+// One block of a page's content
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContentBlock {
+    Heading {
+        level: u8,
+        text: String,
+        #[serde(skip)]
+        markdown: String,
+    },
+    Paragraph {
+        text: String,
+        #[serde(skip)]
+        markdown: String, // Same text with links and emphasis, for to_markdown
+    },
+    List {
+        ordered: bool,
+        items: Vec<Vec<ContentBlock>>, // Each item is a list of blocks, so lists can nest
+    },
+    Table {
+        header: Vec<String>, // Empty when the table has no header row
+        rows: Vec<Vec<String>>,
+    },
+    Image {
+        src: String, // Absolute URL
+        alt: Option<String>,
+    },
+    Quote {
+        blocks: Vec<ContentBlock>,
+    },
+    Code {
+        text: String, // Preformatted text, whitespace kept
+    },
+}
+
+// Text collected between two blocks, both plain and as Markdown
+#[derive(Default)]
+struct Inline {
+    text: String,
+    markdown: String,
+    in_heading: bool, // Collecting a heading's text, where breaks and blocks become spaces
+}
+
+impl Inline {
+    fn push(&mut self, text: &str, markdown: &str) {
+        self.text.push_str(text);
+        self.markdown.push_str(markdown);
+    }
+
+    // Helper function to turn the collected text into a paragraph
+    fn flush(&mut self, blocks: &mut Vec<ContentBlock>) {
+        if self.in_heading {
+            self.push(" ", " ");
+            return;
+        }
+        let text = collapse_whitespace(&self.text);
+        if !text.is_empty() {
+            blocks.push(ContentBlock::Paragraph {
+                text,
+                markdown: collapse_whitespace(&self.markdown),
+            });
+        }
+        self.text.clear();
+        self.markdown.clear();
+    }
+}
+
+// Build the block tree of a document's body; links and images are resolved
+// against `page_url`
+pub fn extract_blocks(document: &Html, page_url: &Url) -> Vec<ContentBlock> {
+    let root = Selector::parse("body")
+        .ok()
+        .and_then(|selector| document.select(&selector).next())
+        .unwrap_or_else(|| document.root_element());
+    collect_blocks(root, page_url, 0)
+}
+
+// Render blocks as Markdown, one blank line between blocks
+pub fn to_markdown(blocks: &[ContentBlock]) -> String {
+    blocks.iter().map(block_markdown).filter(|block| !block.is_empty()).collect::<Vec<_>>().join("\n\n")
+}
+
+fn collect_blocks(element: ElementRef, page_url: &Url, depth: usize) -> Vec<ContentBlock> {
+    let mut blocks = Vec::new();
+    let mut inline = Inline::default();
+    walk(element, page_url, depth, &mut blocks, &mut inline);
+    inline.flush(&mut blocks);
+    blocks
+}
+
+// Helper function to walk the children of an element, turning block
+// elements into blocks and collecting inline content in between
+fn walk(element: ElementRef, page_url: &Url, depth: usize, blocks: &mut Vec<ContentBlock>, inline: &mut Inline) {
+    for child in element.children() {
+        if let Some(text) = child.value().as_text() {
+            inline.push(text, text);
+            continue;
+        }
+        let Some(child) = ElementRef::wrap(child) else {
+            continue;
+        };
+        let child_element = child.value();
+        let name = child_element.name();
+        if HIDDEN_ELEMENTS.contains(&name) || child_element.attr("hidden").is_some() {
+            continue;
+        }
+        if depth >= MAX_DEPTH {
+            let text = child.text().collect::<String>();
+            inline.push(&text, &text);
+            continue;
+        }
+
+        match name {
+            // Inside a heading, block structures fall through to the generic
+            // arms below and are read as part of its text
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" if !inline.in_heading => {
+                inline.flush(blocks);
+                let mut heading = Inline {
+                    in_heading: true,
+                    ..Default::default()
+                };
+                walk(child, page_url, depth + 1, blocks, &mut heading);
+                let text = collapse_whitespace(&heading.text);
+                if !text.is_empty() {
+                    blocks.push(ContentBlock::Heading {
+                        level: name[1..].parse().unwrap_or(1),
+                        text,
+                        markdown: collapse_whitespace(&heading.markdown),
+                    });
+                }
+            }
+            "ul" | "ol" if !inline.in_heading => {
+                inline.flush(blocks);
+                let items: Vec<Vec<ContentBlock>> = child
+                    .children()
+                    .filter_map(ElementRef::wrap)
+                    .filter(|item| item.value().name() == "li")
+                    .map(|item| collect_blocks(item, page_url, depth + 1))
+                    .filter(|item| !item.is_empty())
+                    .collect();
+                if !items.is_empty() {
+                    blocks.push(ContentBlock::List { ordered: name == "ol", items });
+                }
+            }
+            "table" if !inline.in_heading => {
+                inline.flush(blocks);
+                if let Some(table) = simple_table(child) {
+                    blocks.push(table);
+                }
+            }
+            "img" if !inline.in_heading => {
+                if let Some(src) = child_element.attr("src").and_then(|src| page_url.join(src).ok()) {
+                    inline.flush(blocks);
+                    blocks.push(ContentBlock::Image {
+                        src: src.to_string(),
+                        alt: child_element.attr("alt").map(collapse_whitespace).filter(|alt| !alt.is_empty()),
+                    });
+                }
+            }
+            "pre" if !inline.in_heading => {
+                inline.flush(blocks);
+                let text = child.text().collect::<String>();
+                let text = text.trim_matches('\n').trim_end();
+                if !text.is_empty() {
+                    blocks.push(ContentBlock::Code { text: text.to_string() });
+                }
+            }
+            "blockquote" if !inline.in_heading => {
+                inline.flush(blocks);
+                let quoted = collect_blocks(child, page_url, depth + 1);
+                if !quoted.is_empty() {
+                    blocks.push(ContentBlock::Quote { blocks: quoted });
+                }
+            }
+            "br" | "hr" => inline.flush(blocks),
+            "a" => {
+                let text = collapse_whitespace(&child.text().collect::<String>());
+                let href = child_element.attr("href").and_then(|href| page_url.join(href).ok());
+                match href {
+                    Some(href) if !text.is_empty() && matches!(href.scheme(), "http" | "https" | "mailto") => {
+                        push_spaced(inline, child, &text, &format!("[{}]({})", escape_markdown(&text), href));
+                    }
+                    // Links around images or without a usable target keep their content
+                    _ => walk(child, page_url, depth + 1, blocks, inline),
+                }
+            }
+            "strong" | "b" | "em" | "i" | "code" => {
+                let text = collapse_whitespace(&child.text().collect::<String>());
+                if !text.is_empty() {
+                    let markdown = match name {
+                        "strong" | "b" => format!("**{}**", escape_markdown(&text)),
+                        "em" | "i" => format!("*{}*", escape_markdown(&text)),
+                        _ => format!("`{}`", text.replace('`', "")),
+                    };
+                    push_spaced(inline, child, &text, &markdown);
+                }
+            }
+            _ if BLOCK_ELEMENTS.contains(&name) => {
+                inline.flush(blocks);
+                walk(child, page_url, depth + 1, blocks, inline);
+                inline.flush(blocks);
+            }
+            _ => walk(child, page_url, depth + 1, blocks, inline),
+        }
+    }
+}
+
+// Helper function to add an inline element, keeping the whitespace at its
+// edges so "<b> bold</b>" doesn't stick to the word before it
+fn push_spaced(inline: &mut Inline, element: ElementRef, text: &str, markdown: &str) {
+    let raw = element.text().collect::<String>();
+    let before = if raw.starts_with(char::is_whitespace) { " " } else { "" };
+    let after = if raw.ends_with(char::is_whitespace) { " " } else { "" };
+    inline.push(&format!("{}{}{}", before, text, after), &format!("{}{}{}", before, markdown, after));
+}
+
+// Helper function to read a table as a header row and body rows of cell
+// text, with spanned cells repeated (see tables.rs)
+fn simple_table(table: ElementRef) -> Option<ContentBlock> {
+    let grid = tables::table_grid(table)?;
+    if grid.cells.is_empty() {
+        return None;
+    }
+    let header = if grid.header_rows > 0 { tables::column_names(&grid) } else { Vec::new() };
+    let rows = grid.cells[grid.header_rows..].to_vec();
+    Some(ContentBlock::Table { header, rows })
+}
+
+fn block_markdown(block: &ContentBlock) -> String {
+    match block {
+        ContentBlock::Heading { level, markdown, .. } => format!("{} {}", "#".repeat(*level as usize), markdown),
+        ContentBlock::Paragraph { markdown, .. } => markdown.clone(),
+        ContentBlock::List { ordered, items } => items
+            .iter()
+            .enumerate()
+            .map(|(index, item)| {
+                let marker = if *ordered { format!("{}. ", index + 1) } else { "- ".to_string() };
+                let indent = " ".repeat(marker.len());
+                let body = item.iter().map(block_markdown).collect::<Vec<_>>().join("\n");
+                let mut lines = body.lines();
+                let first = lines.next().unwrap_or("");
+                std::iter::once(format!("{}{}", marker, first))
+                    .chain(lines.map(|line| if line.is_empty() { String::new() } else { format!("{}{}", indent, line) }))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+        ContentBlock::Table { header, rows } => {
+            let columns = rows.iter().map(Vec::len).chain([header.len()]).max().unwrap_or(0);
+            if columns == 0 {
+                return String::new();
+            }
+            // Markdown tables need a header row; a headerless table gets an empty one
+            let row_markdown = |cells: &[String]| {
+                let cells: Vec<String> = (0..columns)
+                    .map(|column| cells.get(column).map_or(String::new(), |cell| cell.replace('|', "\\|")))
+                    .collect();
+                format!("| {} |", cells.join(" | "))
+            };
+            let mut lines = vec![row_markdown(header), format!("|{}", " --- |".repeat(columns))];
+            lines.extend(rows.iter().map(|row| row_markdown(row)));
+            lines.join("\n")
+        }
+        ContentBlock::Image { src, alt } => format!("![{}]({})", escape_markdown(alt.as_deref().unwrap_or("")), src),
+        ContentBlock::Quote { blocks } => to_markdown(blocks)
+            .lines()
+            .map(|line| if line.is_empty() { ">".to_string() } else { format!("> {}", line) })
+            .collect::<Vec<_>>()
+            .join("\n"),
+        ContentBlock::Code { text } => format!("```\n{}\n```", text),
+    }
+}
+
+// Helper function to escape the characters that would change the meaning of link and emphasis text
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '[' | ']' | '*' | '_' | '`' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blocks_of(body: &str) -> Vec<ContentBlock> {
+        let document = Html::parse_document(&format!("<html><body>{}</body></html>", body));
+        extract_blocks(&document, &Url::parse("https://example.com/").unwrap())
+    }
+
+    fn heading_texts(blocks: &[ContentBlock]) -> Vec<(u8, &str)> {
+        blocks
+            .iter()
+            .filter_map(|block| match block {
+                ContentBlock::Heading { level, text, .. } => Some((*level, text.as_str())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn heading_keeps_text_around_line_breaks() {
+        let blocks = blocks_of("<h1>Breaking<br>News</h1>");
+        assert_eq!(heading_texts(&blocks), vec![(1, "Breaking News")]);
+        assert_eq!(to_markdown(&blocks), "# Breaking News");
+    }
+
+    #[test]
+    fn heading_reads_block_children_as_text() {
+        let blocks = blocks_of("<h2><span>A</span><p>B</p></h2><p>After</p>");
+        assert_eq!(heading_texts(&blocks), vec![(2, "A B")]);
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(to_markdown(&blocks), "## A B\n\nAfter");
+    }
+
+    #[test]
+    fn heading_keeps_links_and_lists_inline() {
+        let blocks = blocks_of(r#"<h3><a href="/a">Link</a><ul><li>one</li></ul></h3>"#);
+        assert_eq!(heading_texts(&blocks), vec![(3, "Link one")]);
+        assert_eq!(to_markdown(&blocks), "### [Link](https://example.com/a) one");
+    }
+}
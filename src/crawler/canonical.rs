@@ -0,0 +1,174 @@
+use scraper::{Html, Selector};
+use url::{form_urlencoded, Url};
+
+// Query parameters stripped by default; a trailing `*` matches a prefix
+const DEFAULT_TRACKING_PARAMS: [&str; 14] = [
+    "utm_*",
+    "gclid",
+    "gclsrc",
+    "dclid",
+    "fbclid",
+    "msclkid",
+    "yclid",
+    "igshid",
+    "mc_cid",
+    "mc_eid",
+    "_ga",
+    "_gl",
+    "_hsenc",
+    "_hsmi",
+];
+
+// Normalizes URLs so trivially different spellings of a page share one
+// frontier entry and one visited-set key
+#[derive(Debug, Clone)]
+pub struct UrlCanonicalizer {
+    tracking_params: Vec<String>,
+}
+
+impl UrlCanonicalizer {
+    // `tracking_params` replaces the default list when given (an empty list disables stripping)
+    pub fn new(tracking_params: Option<&[String]>) -> Self {
+        let tracking_params = match tracking_params {
+            Some(params) => params.iter().map(|param| param.to_ascii_lowercase()).collect(),
+            None => DEFAULT_TRACKING_PARAMS.iter().map(|param| param.to_string()).collect(),
+        };
+        Self { tracking_params }
+    }
+
+    fn is_tracking_param(&self, name: &str) -> bool {
+        let name = name.to_ascii_lowercase();
+        self.tracking_params.iter().any(|param| match param.strip_suffix('*') {
+            Some(prefix) => name.starts_with(prefix),
+            None => name == *param,
+        })
+    }
+
+    // URL to fetch: lowercase host, no fragment, no tracking parameters and a
+    // sorted query string. Parameters are kept as written (`?amp`, `%20`,
+    // `;`), since servers can tell those spellings apart.
+    pub fn canonicalize(&self, url: &Url) -> Url {
+        let mut canonical = url.clone();
+        canonical.set_fragment(None);
+
+        if let Some(host) = url.host_str() {
+            let lowercase = host.to_lowercase();
+            if lowercase != host {
+                let _ = canonical.set_host(Some(&lowercase));
+            }
+        }
+
+        if let Some(query) = url.query() {
+            // Pairs are filtered and sorted by their decoded name
+            let mut pairs: Vec<(String, &str)> = query
+                .split('&')
+                .filter(|pair| !pair.is_empty())
+                .map(|pair| {
+                    let raw_name = pair.split('=').next().unwrap_or("");
+                    let name = form_urlencoded::parse(raw_name.as_bytes())
+                        .next()
+                        .map(|(name, _)| name.into_owned())
+                        .unwrap_or_default();
+                    (name, pair)
+                })
+                .filter(|(name, _)| !self.is_tracking_param(name))
+                .collect();
+            // Stable sort keeps the order of repeated parameters
+            pairs.sort_by(|a, b| a.0.cmp(&b.0));
+
+            if pairs.is_empty() {
+                canonical.set_query(None);
+            } else {
+                let sorted: Vec<&str> = pairs.iter().map(|(_, pair)| *pair).collect();
+                canonical.set_query(Some(&sorted.join("&")));
+            }
+        }
+
+        canonical
+    }
+
+    // Visited-set key: the canonical URL with a trailing slash ignored, since
+    // most sites serve `/news` and `/news/` as the same page
+    pub fn key(&self, url: &Url) -> String {
+        let mut canonical = self.canonicalize(url);
+        let path = canonical.path().to_string();
+        if path.len() > 1 && path.ends_with('/') {
+            canonical.set_path(path.trim_end_matches('/'));
+        }
+        canonical.to_string()
+    }
+}
+
+// Helper function to read <link rel="canonical"> as an absolute URL
+pub fn find_canonical_link(document: &Html, page_url: &Url) -> Option<Url> {
+    let selector = Selector::parse("link[rel~='canonical'][href]").ok()?;
+    let href = document.select(&selector).next()?.value().attr("href")?;
+    page_url
+        .join(href.trim())
+        .ok()
+        .filter(|url| url.scheme() == "http" || url.scheme() == "https")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(url: &str) -> Url {
+        Url::parse(url).unwrap()
+    }
+
+    #[test]
+    fn key_ignores_tracking_params_fragment_slash_and_host_case() {
+        let canonicalizer = UrlCanonicalizer::new(None);
+        let key = canonicalizer.key(&url("https://example.com/news?id=1"));
+        for spelling in [
+            "https://example.com/news?id=1&utm_source=x&UTM_Medium=y",
+            "https://example.com/news?fbclid=abc&id=1",
+            "https://example.com/news/?id=1",
+            "https://example.com/news?id=1#comments",
+            "https://EXAMPLE.com/news?id=1",
+        ] {
+            assert_eq!(canonicalizer.key(&url(spelling)), key, "{}", spelling);
+        }
+        assert_ne!(canonicalizer.key(&url("https://example.com/news?id=2")), key);
+        assert_eq!(canonicalizer.key(&url("https://example.com/")), "https://example.com/");
+    }
+
+    #[test]
+    fn key_sorts_parameters_but_keeps_repeated_ones_in_order() {
+        let canonicalizer = UrlCanonicalizer::new(None);
+        assert_eq!(
+            canonicalizer.key(&url("https://example.com/?b=2&a=1&b=1")),
+            "https://example.com/?a=1&b=2&b=1"
+        );
+    }
+
+    #[test]
+    fn canonicalize_keeps_parameters_as_written() {
+        let canonicalizer = UrlCanonicalizer::new(None);
+        for (original, canonical) in [
+            ("https://example.com/?amp", "https://example.com/?amp"),
+            ("https://example.com/?q=a%20b", "https://example.com/?q=a%20b"),
+            ("https://example.com/?q=a+b", "https://example.com/?q=a+b"),
+            ("https://example.com/?id=1;2", "https://example.com/?id=1;2"),
+            ("https://example.com/?utm_source=x", "https://example.com/"),
+            ("https://example.com/?z=1&&a=2", "https://example.com/?a=2&z=1"),
+        ] {
+            assert_eq!(canonicalizer.canonicalize(&url(original)).as_str(), canonical);
+        }
+    }
+
+    #[test]
+    fn custom_tracking_params_replace_the_defaults() {
+        let canonicalizer = UrlCanonicalizer::new(Some(&["Ref".to_string(), "src_*".to_string()]));
+        assert_eq!(
+            canonicalizer.canonicalize(&url("https://example.com/?ref=a&src_x=1&utm_source=b")).as_str(),
+            "https://example.com/?utm_source=b"
+        );
+        let disabled = UrlCanonicalizer::new(Some(&[]));
+        assert_eq!(
+            disabled.canonicalize(&url("https://example.com/?utm_source=b")).as_str(),
+            "https://example.com/?utm_source=b"
+        );
+    }
+}
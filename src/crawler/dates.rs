@@ -0,0 +1,364 @@
+use chrono::{DateTime, Datelike, NaiveDate};
+use regex::Regex;
+use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+use url::Url;
+
+use super::normalize;
+
+// Years accepted from URLs and free text; other four-digit numbers are not dates
+const MIN_YEAR: i32 = 1990;
+const MAX_YEAR: i32 = 2100;
+
+// Elements whose text usually holds a human-readable publication date
+const DATE_TEXT_SELECTOR: &str =
+    "time:not([datetime]), [itemprop=\"datePublished\"], [class*=\"date\"], [class*=\"published\"], [class*=\"posted\"]";
+
+// Month names and abbreviations, without diacritics, in English, Indonesian,
+// Spanish, French, German and Portuguese
+const MONTH_NAMES: [&[&str]; 12] = [
+    &["january", "jan", "januari", "enero", "ene", "janvier", "janv", "januar", "janeiro"],
+    &["february", "feb", "februari", "febrero", "fevrier", "fevr", "februar", "fevereiro", "fev"],
+    &["march", "mar", "maret", "marzo", "mars", "marz", "marco"],
+    &["april", "apr", "abril", "abr", "avril", "avr"],
+    &["may", "mei", "mayo", "mai", "maio"],
+    &["june", "jun", "juni", "junio", "juin", "junho"],
+    &["july", "jul", "juli", "julio", "juillet", "juil", "julho"],
+    &["august", "aug", "agustus", "agu", "agt", "agosto", "ago", "aout"],
+    &["september", "sep", "sept", "septiembre", "setiembre", "septembre", "setembro"],
+    &["october", "oct", "oktober", "okt", "octubre", "octobre", "outubro"],
+    &["november", "nov", "noviembre", "novembre", "novembro"],
+    &["december", "dec", "desember", "des", "diciembre", "dic", "decembre", "dezember", "dez", "dezembro"],
+];
+
+// Short words allowed between the parts of a textual date ("12 de mayo de 2024")
+const DATE_FILLER_WORDS: [&str; 4] = ["de", "del", "of", "the"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DateKind {
+    Published,
+    Modified,
+}
+
+// Where a page date was found
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DateSource {
+    Meta,   // <meta> tag such as article:published_time
+    JsonLd, // datePublished/dateModified in a JSON-LD block
+    Time,   // <time datetime="...">
+    Header, // HTTP Last-Modified response header
+    Feed,   // RSS/Atom entry that linked to the page
+    Url,    // Date in the URL path, e.g. /2024/05/12/
+    Text,   // Written-out date in the page text, e.g. "12 Mei 2024"
+}
+
+// A date found for a page. The first parsed date of each kind becomes the
+// page's published_date/last_modified (see preferred).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PageDate {
+    pub kind: DateKind,
+    pub source: DateSource,
+    pub value: String,             // Date as found on the page or in the header
+    pub date: Option<NaiveDate>,   // Calendar date of `value`, if it could be parsed
+}
+
+impl PageDate {
+    pub fn new(kind: DateKind, source: DateSource, value: &str) -> Self {
+        let value = value.trim().to_string();
+        Self {
+            kind,
+            source,
+            date: parse_date_value(&value),
+            value,
+        }
+    }
+}
+
+// Collect every date of a page, in order of preference: meta tags, JSON-LD
+// (the page's parsed blocks), the first <time datetime>, the Last-Modified
+// header, the feed entry, the URL and finally dates written out in the page
+// text
+pub fn extract_page_dates(
+    document: &Html,
+    json_ld: &[serde_json::Value],
+    page_url: &Url,
+    header_last_modified: Option<&str>,
+    feed_published: Option<&str>,
+) -> Vec<PageDate> {
+    let mut dates = Vec::new();
+
+    if let Ok(meta_selector) = Selector::parse("meta[content]") {
+        for meta in document.select(&meta_selector) {
+            let element = meta.value();
+            let key = element.attr("property").or_else(|| element.attr("name")).unwrap_or("");
+            let kind = match key.to_ascii_lowercase().as_str() {
+                "article:modified_time" | "article:updated_time" | "og:updated_time" | "last-modified" | "date-modified" => {
+                    DateKind::Modified
+                }
+                "article:published_time" | "date" | "publish-date" | "publication-date" | "pubdate" | "dc.date" => {
+                    DateKind::Published
+                }
+                _ => continue,
+            };
+            if let Some(content) = element.attr("content") {
+                dates.push(PageDate::new(kind, DateSource::Meta, content));
+            }
+        }
+    }
+
+    for block in json_ld {
+        collect_json_ld_dates(block, &mut dates);
+    }
+
+    // Pages often carry many <time> elements (comments, related articles);
+    // only the first one is taken as the publication date
+    if let Ok(time_selector) = Selector::parse("time[datetime]") {
+        if let Some(datetime) = document.select(&time_selector).find_map(|time| time.value().attr("datetime")) {
+            dates.push(PageDate::new(DateKind::Published, DateSource::Time, datetime));
+        }
+    }
+
+    if let Some(value) = header_last_modified {
+        dates.push(PageDate::new(DateKind::Modified, DateSource::Header, value));
+    }
+    if let Some(value) = feed_published {
+        dates.push(PageDate::new(DateKind::Published, DateSource::Feed, value));
+    }
+    dates.extend(url_date(page_url));
+    dates.extend(text_date(document));
+
+    dates
+}
+
+// The preferred date of a kind: the first one that could be parsed
+pub fn preferred(dates: &[PageDate], kind: DateKind) -> Option<&PageDate> {
+    dates.iter().find(|date| date.kind == kind && date.date.is_some())
+}
+
+// Parse a date value found on a page, in a header, sitemap or feed: RFC 3339,
+// RFC 2822, ISO dates with any time suffix, slashed dates, and written-out
+// dates such as "May 12, 2024" or "12 Mei 2024"
+pub fn parse_date_value(date_str: &str) -> Option<NaiveDate> {
+    let date_str = date_str.trim();
+    DateTime::parse_from_rfc3339(date_str)
+        .or_else(|_| DateTime::parse_from_rfc2822(date_str))
+        .map(|dt| dt.date_naive())
+        .or_else(|_| NaiveDate::parse_from_str(date_str, "%Y-%m-%d"))
+        .or_else(|_| NaiveDate::parse_from_str(date_str, "%Y/%m/%d"))
+        .or_else(|_| NaiveDate::parse_from_str(date_str, "%Y.%m.%d"))
+        .ok()
+        // W3C datetimes may omit seconds (2024-05-12T10:00+07:00)
+        .or_else(|| {
+            date_str
+                .get(..10)
+                .filter(|_| date_str[10..].starts_with(['T', ' ']))
+                .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok())
+        })
+        .or_else(|| find_textual_date(date_str).map(|(date, _)| date))
+}
+
+// Helper function to walk a JSON-LD value (objects, arrays and @graph) for
+// publication and modification dates
+fn collect_json_ld_dates(value: &serde_json::Value, dates: &mut Vec<PageDate>) {
+    match value {
+        serde_json::Value::Object(object) => {
+            for (key, child) in object {
+                let kind = match key.as_str() {
+                    "datePublished" | "dateCreated" | "uploadDate" => Some(DateKind::Published),
+                    "dateModified" => Some(DateKind::Modified),
+                    _ => None,
+                };
+                match (kind, child) {
+                    (Some(kind), serde_json::Value::String(date)) => {
+                        dates.push(PageDate::new(kind, DateSource::JsonLd, date));
+                    }
+                    _ => collect_json_ld_dates(child, dates),
+                }
+            }
+        }
+        serde_json::Value::Array(items) => {
+            for item in items {
+                collect_json_ld_dates(item, dates);
+            }
+        }
+        _ => {}
+    }
+}
+
+// Helper function to read a /2024/05/12/ or /2024-05-12 style date from a URL path
+fn url_date(page_url: &Url) -> Option<PageDate> {
+    static URL_DATE: OnceLock<Regex> = OnceLock::new();
+    let pattern = URL_DATE.get_or_init(|| {
+        Regex::new(r"/(\d{4})(?:/(\d{1,2})/(\d{1,2})|-(\d{1,2})-(\d{1,2}))(?:[/\-_.]|$)").expect("URL date pattern is valid")
+    });
+
+    pattern.captures_iter(page_url.path()).find_map(|captures| {
+        let part = |slashed: usize, dashed: usize| captures.get(slashed).or_else(|| captures.get(dashed))?.as_str().parse().ok();
+        let date = checked_date(captures[1].parse().ok()?, part(2, 4)?, part(3, 5)?)?;
+        let value = captures[0].trim_start_matches('/').trim_end_matches(['/', '-', '_', '.']);
+        Some(PageDate {
+            kind: DateKind::Published,
+            source: DateSource::Url,
+            value: value.to_string(),
+            date: Some(date),
+        })
+    })
+}
+
+// Helper function to find a written-out date in elements that usually hold
+// one (bylines, date labels); the rest of the page text is not searched
+fn text_date(document: &Html) -> Option<PageDate> {
+    let selector = Selector::parse(DATE_TEXT_SELECTOR).ok()?;
+    document.select(&selector).find_map(|element| {
+        let text = element.text().collect::<Vec<_>>().join(" ");
+        let (date, value) = find_textual_date(&text)?;
+        Some(PageDate {
+            kind: DateKind::Published,
+            source: DateSource::Text,
+            value,
+            date: Some(date),
+        })
+    })
+}
+
+// Find the first written-out date in a text: day-month-year ("12 Mei 2024",
+// "12 de mayo de 2024") or month-day-year ("May 12, 2024"). Returns the date
+// and the text it was read from.
+fn find_textual_date(text: &str) -> Option<(NaiveDate, String)> {
+    let words: Vec<(usize, usize, String)> = normalize::word_ranges(text)
+        .into_iter()
+        .map(|(start, end)| (start, end, fold_word(&text[start..end])))
+        .filter(|(_, _, word)| !DATE_FILLER_WORDS.contains(&word.as_str()))
+        .collect();
+
+    for (index, (_, _, word)) in words.iter().enumerate() {
+        let Some(month) = month_number(word) else {
+            continue;
+        };
+        let before = index.checked_sub(1).and_then(|i| words.get(i));
+        let after = words.get(index + 1);
+        let after_next = words.get(index + 2);
+
+        // Day before the month, year after it
+        if let (Some(day_word), Some(year_word)) = (before, after) {
+            if let (Some(day), Some(year)) = (day_number(&day_word.2), year_number(&year_word.2)) {
+                if let Some(date) = checked_date(year, month, day) {
+                    return Some((date, text[day_word.0..year_word.1].to_string()));
+                }
+            }
+        }
+        // Day and year after the month
+        if let (Some(day_word), Some(year_word)) = (after, after_next) {
+            if let (Some(day), Some(year)) = (day_number(&day_word.2), year_number(&year_word.2)) {
+                if let Some(date) = checked_date(year, month, day) {
+                    return Some((date, text[words[index].0..year_word.1].to_string()));
+                }
+            }
+        }
+    }
+    None
+}
+
+// Helper function to lowercase a word and strip its diacritics ("Février" → "fevrier")
+fn fold_word(word: &str) -> String {
+    word.to_lowercase().nfd().filter(|c| !is_combining_mark(*c)).collect()
+}
+
+fn month_number(word: &str) -> Option<u32> {
+    MONTH_NAMES
+        .iter()
+        .position(|names| names.contains(&word))
+        .map(|index| index as u32 + 1)
+}
+
+// Helper function to read a day of the month, allowing English ordinals ("12th")
+fn day_number(word: &str) -> Option<u32> {
+    let digits = word.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let suffix = &word[digits.len()..];
+    if digits.is_empty() || digits.len() > 2 || !matches!(suffix, "" | "st" | "nd" | "rd" | "th") {
+        return None;
+    }
+    digits.parse().ok()
+}
+
+fn year_number(word: &str) -> Option<i32> {
+    if word.len() != 4 {
+        return None;
+    }
+    word.parse().ok()
+}
+
+// Helper function to build a date, rejecting impossible days and unlikely years
+fn checked_date(year: i32, month: u32, day: u32) -> Option<NaiveDate> {
+    NaiveDate::from_ymd_opt(year, month, day).filter(|date| (MIN_YEAR..=MAX_YEAR).contains(&date.year()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> Option<NaiveDate> {
+        NaiveDate::from_ymd_opt(year, month, day)
+    }
+
+    #[test]
+    fn machine_formats() {
+        assert_eq!(parse_date_value("2024-05-12T10:30:00+07:00"), date(2024, 5, 12));
+        assert_eq!(parse_date_value("Sun, 12 May 2024 10:30:00 GMT"), date(2024, 5, 12));
+        assert_eq!(parse_date_value(" 2024-05-12 "), date(2024, 5, 12));
+        assert_eq!(parse_date_value("2024/05/12"), date(2024, 5, 12));
+        assert_eq!(parse_date_value("2024.05.12"), date(2024, 5, 12));
+        assert_eq!(parse_date_value("2024-05-12 10:30:00"), date(2024, 5, 12));
+    }
+
+    #[test]
+    fn w3c_datetimes_without_seconds() {
+        assert_eq!(parse_date_value("2024-05-12T10:00+07:00"), date(2024, 5, 12));
+        assert_eq!(parse_date_value("2024-05-12T10:00Z"), date(2024, 5, 12));
+        assert_eq!(parse_date_value("2024-05-12T10:00"), date(2024, 5, 12));
+    }
+
+    #[test]
+    fn written_out_dates() {
+        assert_eq!(parse_date_value("12 de mayo de 2024"), date(2024, 5, 12));
+        assert_eq!(parse_date_value("May 12th, 2024"), date(2024, 5, 12));
+        assert_eq!(parse_date_value("May 12, 2024"), date(2024, 5, 12));
+        assert_eq!(parse_date_value("12 Mei 2024"), date(2024, 5, 12));
+        assert_eq!(parse_date_value("1 février 2024"), date(2024, 2, 1));
+        assert_eq!(parse_date_value("12. März 2024"), date(2024, 3, 12));
+    }
+
+    #[test]
+    fn invalid_dates() {
+        assert_eq!(parse_date_value(""), None);
+        assert_eq!(parse_date_value("not a date"), None);
+        assert_eq!(parse_date_value("2024-02-30"), None);
+        assert_eq!(parse_date_value("31 June 2024"), None);
+        assert_eq!(parse_date_value("May 2024"), None);
+    }
+
+    #[test]
+    fn textual_date_in_running_text() {
+        let (found, value) = find_textual_date("Posted by Jane on Tuesday, May 12th, 2024 at 10:00").unwrap();
+        assert_eq!(Some(found), date(2024, 5, 12));
+        assert_eq!(value, "May 12th, 2024");
+        let (found, value) = find_textual_date("Publicado el 12 de mayo de 2024").unwrap();
+        assert_eq!(Some(found), date(2024, 5, 12));
+        assert_eq!(value, "12 de mayo de 2024");
+        assert!(find_textual_date("Updated 5 minutes ago").is_none());
+    }
+
+    #[test]
+    fn dates_from_urls() {
+        let url_date_of = |url: &str| url_date(&Url::parse(url).unwrap()).and_then(|date| date.date);
+        assert_eq!(url_date_of("https://example.com/2024/05/12/story"), date(2024, 5, 12));
+        assert_eq!(url_date_of("https://example.com/news/2024-05-12-story.html"), date(2024, 5, 12));
+        assert_eq!(url_date_of("https://example.com/2024/13/40/story"), None);
+        assert_eq!(url_date_of("https://example.com/products/12345"), None);
+    }
+}
@@ -0,0 +1,114 @@
+use regex::Regex;
+use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use url::Url;
+
+use super::text::collapse_whitespace;
+use super::CrawlerError;
+
+// Attributes holding URLs, which are resolved against the page URL
+const URL_ATTRIBUTES: [&str; 4] = ["href", "src", "action", "data-src"];
+
+// Named field pulled out of every page, e.g.
+// {"name": "price", "selector": ".price", "pattern": "[0-9.,]+"}
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtractionRule {
+    pub name: String,
+    pub selector: String, // CSS selector
+    #[serde(default)]
+    pub attribute: Option<String>, // Attribute to read; the element's text when absent
+    #[serde(default)]
+    pub list: bool, // Every matching element instead of the first one
+    #[serde(default)]
+    pub pattern: Option<String>, // Regex applied to each value: the first capture group (or the whole match) is kept, non-matching values are dropped
+}
+
+// Value of one rule on one page: null or [] when nothing matched
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ExtractedValue {
+    Single(Option<String>),
+    List(Vec<String>),
+}
+
+struct CompiledRule {
+    name: String,
+    selector: Selector,
+    attribute: Option<String>,
+    list: bool,
+    pattern: Option<Regex>,
+}
+
+// Extraction rules compiled once per crawl
+pub struct Extractor {
+    rules: Vec<CompiledRule>,
+}
+
+impl Extractor {
+    pub fn compile(rules: &[ExtractionRule]) -> Result<Self, CrawlerError> {
+        let mut names = HashSet::new();
+        let mut compiled = Vec::with_capacity(rules.len());
+        for rule in rules {
+            let name = rule.name.trim();
+            if name.is_empty() {
+                return Err(CrawlerError::SelectorError("extraction rules need a name".to_string()));
+            }
+            if !names.insert(name) {
+                return Err(CrawlerError::SelectorError(format!("duplicate extraction rule '{}'", name)));
+            }
+            let selector = Selector::parse(&rule.selector)
+                .map_err(|e| CrawlerError::SelectorError(format!("rule '{}': {}: {}", name, rule.selector, e)))?;
+            let pattern = rule
+                .pattern
+                .as_deref()
+                .map(Regex::new)
+                .transpose()
+                .map_err(|e| CrawlerError::SelectorError(format!("rule '{}': invalid pattern: {}", name, e)))?;
+            compiled.push(CompiledRule {
+                name: name.to_string(),
+                selector,
+                attribute: rule.attribute.as_deref().map(str::trim).filter(|attribute| !attribute.is_empty()).map(str::to_string),
+                list: rule.list,
+                pattern,
+            });
+        }
+        Ok(Self { rules: compiled })
+    }
+
+    // Run every rule against a page
+    pub fn extract(&self, document: &Html, page_url: &Url) -> BTreeMap<String, ExtractedValue> {
+        self.rules
+            .iter()
+            .map(|rule| {
+                let mut values = document.select(&rule.selector).filter_map(|element| {
+                    let raw = match &rule.attribute {
+                        Some(attribute) => {
+                            let value = element.value().attr(attribute)?.trim();
+                            if URL_ATTRIBUTES.contains(&attribute.as_str()) {
+                                page_url.join(value).map(String::from).unwrap_or_else(|_| value.to_string())
+                            } else {
+                                value.to_string()
+                            }
+                        }
+                        None => collapse_whitespace(&element.text().collect::<Vec<_>>().join(" ")),
+                    };
+                    let value = match &rule.pattern {
+                        Some(pattern) => {
+                            let captures = pattern.captures(&raw)?;
+                            captures.get(1).or_else(|| captures.get(0))?.as_str().to_string()
+                        }
+                        None => raw,
+                    };
+                    (!value.is_empty()).then_some(value)
+                });
+                let value = if rule.list {
+                    ExtractedValue::List(values.collect())
+                } else {
+                    ExtractedValue::Single(values.next())
+                };
+                (rule.name.clone(), value)
+            })
+            .collect()
+    }
+}
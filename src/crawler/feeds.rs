@@ -0,0 +1,183 @@
+use chrono::{DateTime, NaiveDate};
+use reqwest::Client;
+use scraper::{Html, Selector};
+use std::collections::HashSet;
+use tokio::time::Instant;
+use url::Url;
+
+use super::fetch::{self, RetryPolicy};
+use super::robots::RobotsCache;
+use super::dates::parse_date_value;
+use super::{date_in_range, CrawlerError};
+
+// Feeds read per domain when a page advertises several
+const MAX_FEEDS: usize = 10;
+
+// Largest feed or seed page read, the same limit as for sitemaps
+const MAX_FEED_BYTES: usize = 50 * 1024 * 1024;
+
+#[derive(Debug)]
+pub struct FeedEntry {
+    pub url: Url,
+    pub published: Option<String>, // RFC 3339 publication (or update) date from the feed
+}
+
+// Collect entries from the feeds of a site. The seed may be a feed itself or
+// an HTML page advertising feeds via <link rel="alternate">. Entries dated
+// outside the range are dropped; undated entries are kept.
+pub async fn collect_entries(
+    client: &Client,
+    seed_url: &Url,
+    policy: &RetryPolicy,
+    robots_cache: &RobotsCache,
+    deadline: Option<Instant>,
+    date_from: Option<NaiveDate>,
+    date_to: Option<NaiveDate>,
+) -> Result<Vec<FeedEntry>, CrawlerError> {
+    let (seed_final_url, seed_body) = fetch_text(client, seed_url, policy, robots_cache, deadline).await?;
+
+    let mut entries = Vec::new();
+    if let Ok(seed_entries) = parse_feed(&seed_body, &seed_final_url) {
+        entries = seed_entries;
+    } else {
+        let feed_urls = discover_feed_links(&Html::parse_document(&seed_body), &seed_final_url);
+        if feed_urls.is_empty() {
+            return Err(CrawlerError::FeedError(format!("No RSS or Atom feed found at {}", seed_url)));
+        }
+
+        for feed_url in feed_urls.into_iter().take(MAX_FEEDS) {
+            let (feed_final_url, feed_body) = match fetch_text(client, &feed_url, policy, robots_cache, deadline).await {
+                Ok(feed) => feed,
+                Err(e) => {
+                    eprintln!("Failed to fetch feed {}: {}", feed_url, e);
+                    continue;
+                }
+            };
+            match parse_feed(&feed_body, &feed_final_url) {
+                Ok(feed_entries) => entries.extend(feed_entries),
+                Err(e) => eprintln!("Failed to parse feed {}: {}", feed_url, e),
+            }
+        }
+    }
+
+    // Several feeds often list the same article
+    let mut seen = HashSet::new();
+    entries.retain(|entry| seen.insert(entry.url.to_string()));
+
+    entries.retain(|entry| {
+        entry
+            .published
+            .as_deref()
+            .and_then(parse_date_value)
+            .is_none_or(|date| date_in_range(date, date_from, date_to))
+    });
+
+    Ok(entries)
+}
+
+// Helper function to fetch a document as text, returning its final URL
+async fn fetch_text(
+    client: &Client,
+    url: &Url,
+    policy: &RetryPolicy,
+    robots_cache: &RobotsCache,
+    deadline: Option<Instant>,
+) -> Result<(Url, String), CrawlerError> {
+    let outcome = fetch::fetch_page(client, url, policy, robots_cache, None, deadline).await;
+    let response = outcome.result?;
+    if !response.status().is_success() {
+        return Err(CrawlerError::HttpStatus {
+            status: response.status().as_u16(),
+            url: outcome.final_url.to_string(),
+        });
+    }
+    let body = fetch::until_deadline(deadline, fetch::read_capped(response, MAX_FEED_BYTES))
+        .await
+        .ok_or(CrawlerError::TimeoutError)??;
+    drop(outcome.host_permit);
+    let Some(bytes) = body else {
+        return Err(CrawlerError::FeedError(format!("{} is larger than {} bytes", outcome.final_url, MAX_FEED_BYTES)));
+    };
+    Ok((outcome.final_url, String::from_utf8_lossy(&bytes).into_owned()))
+}
+
+// Helper function to find feeds advertised with <link rel="alternate">
+fn discover_feed_links(document: &Html, page_url: &Url) -> Vec<Url> {
+    let mut feed_urls = Vec::new();
+
+    if let Ok(link_selector) = Selector::parse("link[rel~='alternate'][href]") {
+        for link in document.select(&link_selector) {
+            let feed_type = link.value().attr("type").unwrap_or("").to_ascii_lowercase();
+            if feed_type != "application/rss+xml" && feed_type != "application/atom+xml" {
+                continue;
+            }
+            if let Some(feed_url) = link.value().attr("href").and_then(|href| page_url.join(href).ok()) {
+                if !feed_urls.contains(&feed_url) {
+                    feed_urls.push(feed_url);
+                }
+            }
+        }
+    }
+
+    feed_urls
+}
+
+// Parse RSS 2.0 (and RSS 1.0/RDF) items or Atom entries
+fn parse_feed(xml: &str, feed_url: &Url) -> Result<Vec<FeedEntry>, CrawlerError> {
+    let document = roxmltree::Document::parse(xml).map_err(|e| CrawlerError::FeedError(e.to_string()))?;
+    let root = document.root_element();
+
+    let entries = if root.has_tag_name("rss") || root.has_tag_name("RDF") {
+        root.descendants()
+            .filter(|node| node.has_tag_name("item"))
+            .filter_map(|item| {
+                // Fall back to a permalink <guid> when <link> is missing
+                let link = child_text(item, "link").or_else(|| {
+                    item.children()
+                        .find(|child| child.has_tag_name("guid") && child.attribute("isPermaLink") != Some("false"))
+                        .and_then(|guid| guid.text())
+                        .map(str::trim)
+                })?;
+                let url = feed_url.join(link).ok()?;
+                let published = child_text(item, "pubDate")
+                    .and_then(|date| DateTime::parse_from_rfc2822(date).ok())
+                    .map(|date| date.to_rfc3339())
+                    // RSS 1.0 uses <dc:date> in W3C format
+                    .or_else(|| child_text(item, "date").map(str::to_string));
+                Some(FeedEntry { url, published })
+            })
+            .collect()
+    } else if root.has_tag_name("feed") {
+        root.children()
+            .filter(|node| node.has_tag_name("entry"))
+            .filter_map(|entry| {
+                let href = entry
+                    .children()
+                    .filter(|child| child.has_tag_name("link"))
+                    .find(|link| link.attribute("rel").is_none_or(|rel| rel == "alternate"))
+                    .and_then(|link| link.attribute("href"))?;
+                let url = feed_url.join(href).ok()?;
+                let published = child_text(entry, "published")
+                    .or_else(|| child_text(entry, "updated"))
+                    .map(str::to_string);
+                Some(FeedEntry { url, published })
+            })
+            .collect()
+    } else {
+        return Err(CrawlerError::FeedError(format!(
+            "Unsupported feed format <{}>",
+            root.tag_name().name()
+        )));
+    };
+
+    Ok(entries)
+}
+
+// Helper function to read the trimmed text of a named child element
+fn child_text<'a>(node: roxmltree::Node<'a, '_>, name: &str) -> Option<&'a str> {
+    node.children()
+        .find(|child| child.has_tag_name(name))
+        .and_then(|child| child.text())
+        .map(str::trim)
+        .filter(|text| !text.is_empty())
+}
@@ -0,0 +1,313 @@
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use reqwest::header::{LOCATION, RETRY_AFTER};
+use reqwest::{Client, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::time::Duration;
+use tokio::time::Instant;
+use url::Url;
+
+use super::politeness::{self, HostPermit};
+use super::robots::RobotsCache;
+use super::scope::UrlFilter;
+use super::CrawlerError;
+
+// Redirect hops followed before giving up on a URL
+const MAX_REDIRECTS: usize = 10;
+
+// Upper bounds for the retry settings of a request
+const MAX_RETRIES: u32 = 10;
+const MAX_BACKOFF_MS: u64 = 5 * 60 * 1000;
+
+// Retry settings for a single page fetch, configurable per request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32, // Retries after the first attempt
+    #[serde(default = "default_initial_backoff_ms")]
+    pub initial_backoff_ms: u64, // Base delay, doubled on every retry
+    #[serde(default = "default_max_backoff_ms")]
+    pub max_backoff_ms: u64, // Cap for the backoff and for honored Retry-After values
+}
+
+fn default_max_retries() -> u32 {
+    2
+}
+
+fn default_initial_backoff_ms() -> u64 {
+    500
+}
+
+fn default_max_backoff_ms() -> u64 {
+    30_000
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: default_max_retries(),
+            initial_backoff_ms: default_initial_backoff_ms(),
+            max_backoff_ms: default_max_backoff_ms(),
+        }
+    }
+}
+
+impl RetryPolicy {
+    // The same policy with every setting capped, so a request cannot keep a
+    // fetch retrying for hours
+    pub fn clamped(&self) -> Self {
+        let max_backoff_ms = self.max_backoff_ms.min(MAX_BACKOFF_MS);
+        Self {
+            max_retries: self.max_retries.min(MAX_RETRIES),
+            initial_backoff_ms: self.initial_backoff_ms.min(max_backoff_ms),
+            max_backoff_ms,
+        }
+    }
+
+    // Exponential backoff with "equal jitter": half fixed, half random
+    fn backoff(&self, attempt: u32) -> Duration {
+        let exponential = self
+            .initial_backoff_ms
+            .saturating_mul(1u64 << attempt.saturating_sub(1).min(20))
+            .min(self.max_backoff_ms);
+        let half = exponential / 2;
+        let jitter = rand::thread_rng().gen_range(0..=exponential - half);
+        Duration::from_millis(half + jitter)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FetchAttempt {
+    pub url: String,                 // URL requested (differs from the page URL after redirects)
+    pub attempt: u32,                // 1-based attempt number for this URL
+    pub status: Option<u16>,         // HTTP status, if a response was received
+    pub error: Option<String>,       // Transport error, if no response was received
+    pub duration_ms: u64,
+    pub retry_delay_ms: Option<u64>, // Wait before the next attempt, if one was scheduled
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RedirectHop {
+    pub url: String,
+    pub status: u16,
+}
+
+pub struct FetchOutcome {
+    pub result: Result<Response, CrawlerError>,
+    pub attempts: Vec<FetchAttempt>,
+    pub final_url: Url,                  // URL of the last request made
+    pub redirect_chain: Vec<RedirectHop>, // Redirect responses that led to `final_url`
+    pub host_permit: Option<HostPermit>, // In-flight slot of the final response; drop it once the body is read
+}
+
+// Helper function to decide whether a response status is worth retrying
+fn is_retryable_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+// Helper function to read Retry-After as delta-seconds or an HTTP date
+fn parse_retry_after(response: &Response) -> Option<Duration> {
+    let value = response.headers().get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = DateTime::parse_from_rfc2822(value).ok()?.with_timezone(&Utc);
+    Some((date - Utc::now()).to_std().unwrap_or_default())
+}
+
+// Helper function to run a request future, giving up at the crawl deadline.
+// Returns None if the deadline passed first.
+pub async fn until_deadline<F: Future>(deadline: Option<Instant>, future: F) -> Option<F::Output> {
+    match deadline {
+        Some(deadline) => tokio::time::timeout_at(deadline, future).await.ok(),
+        None => Some(future.await),
+    }
+}
+
+// Helper function to read a response body, giving up once it grows past
+// `max_bytes`. Returns None for an oversized body.
+pub async fn read_capped(mut response: Response, max_bytes: usize) -> Result<Option<Vec<u8>>, reqwest::Error> {
+    let mut bytes = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        if bytes.len() + chunk.len() > max_bytes {
+            return Ok(None);
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    Ok(Some(bytes))
+}
+
+// Fetch a page, following redirects manually so every hop is checked
+// against robots.txt and recorded. With a `scope` of (filter, seed URL),
+// redirect targets must also pass the crawl scope and URL patterns. The
+// client must not follow redirects itself. Non-2xx final responses are returned as-is for the caller to
+// classify.
+pub async fn fetch_page(
+    client: &Client,
+    url: &Url,
+    policy: &RetryPolicy,
+    robots_cache: &RobotsCache,
+    scope: Option<(&UrlFilter, &Url)>,
+    deadline: Option<Instant>,
+) -> FetchOutcome {
+    let mut attempts = Vec::new();
+    let mut redirect_chain = Vec::new();
+    let mut current_url = url.clone();
+
+    loop {
+        let robots = robots_cache.rules_for(client, &current_url).await;
+        if !robots.is_allowed(&current_url) {
+            let err = if robots.is_unavailable() {
+                CrawlerError::RobotsUnavailable(current_url.to_string())
+            } else {
+                CrawlerError::RobotsDisallowed(current_url.to_string())
+            };
+            return FetchOutcome {
+                result: Err(err),
+                attempts,
+                final_url: current_url,
+                redirect_chain,
+                host_permit: None,
+            };
+        }
+
+        let (result, host_permit) = match fetch_with_retry(client, &current_url, policy, robots.crawl_delay(), deadline, &mut attempts).await {
+            Ok((response, host_permit)) => (Ok(response), Some(host_permit)),
+            Err(err) => (Err(err), None),
+        };
+
+        // Follow redirects that carry a usable Location header
+        let location = match &result {
+            Ok(response) if response.status().is_redirection() => response
+                .headers()
+                .get(LOCATION)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| current_url.join(value).ok()),
+            _ => None,
+        };
+        let (Some(next_url), Ok(response)) = (location, &result) else {
+            return FetchOutcome {
+                result,
+                attempts,
+                final_url: current_url,
+                redirect_chain,
+                host_permit,
+            };
+        };
+
+        redirect_chain.push(RedirectHop {
+            url: current_url.to_string(),
+            status: response.status().as_u16(),
+        });
+        if redirect_chain.len() > MAX_REDIRECTS {
+            return FetchOutcome {
+                result: Err(CrawlerError::TooManyRedirects(url.to_string())),
+                attempts,
+                final_url: current_url,
+                redirect_chain,
+                host_permit: None,
+            };
+        }
+        if let Some(reason) = scope.and_then(|(url_filter, seed_url)| url_filter.redirect_rejection(&next_url, seed_url)) {
+            return FetchOutcome {
+                result: Err(CrawlerError::RedirectSkipped {
+                    url: next_url.to_string(),
+                    reason,
+                }),
+                attempts,
+                final_url: current_url,
+                redirect_chain,
+                host_permit: None,
+            };
+        }
+        current_url = next_url;
+    }
+}
+
+// Fetch a single URL through the politeness scheduler, retrying connect
+// errors, timeouts, 429 and 5xx responses. A retryable response that runs out
+// of retries is returned as-is, together with its in-flight permit so the
+// body can be read before the slot is released; CrawlerError::TimeoutError
+// means the deadline was reached before or during an attempt.
+async fn fetch_with_retry(
+    client: &Client,
+    url: &Url,
+    policy: &RetryPolicy,
+    crawl_delay: Option<Duration>,
+    deadline: Option<Instant>,
+    attempts: &mut Vec<FetchAttempt>,
+) -> Result<(Response, HostPermit), CrawlerError> {
+    let mut attempt = 0;
+
+    loop {
+        attempt += 1;
+
+        let Some(host_permit) = politeness::global().acquire(url, crawl_delay, deadline).await else {
+            return Err(CrawlerError::TimeoutError);
+        };
+
+        let started = Instant::now();
+        let sent = until_deadline(deadline, client.get(url.clone()).send()).await;
+        let duration_ms = started.elapsed().as_millis() as u64;
+        let Some(sent) = sent else {
+            attempts.push(FetchAttempt {
+                url: url.to_string(),
+                attempt,
+                status: None,
+                error: Some(CrawlerError::TimeoutError.to_string()),
+                duration_ms,
+                retry_delay_ms: None,
+            });
+            return Err(CrawlerError::TimeoutError);
+        };
+
+        let (status, error, retry_delay) = match &sent {
+            Ok(response) => {
+                let status = response.status();
+                host_permit.record_status(status);
+                let retry_delay = if is_retryable_status(status) {
+                    match parse_retry_after(response) {
+                        // A server asking for more than we are willing to wait is not retried
+                        Some(wait) if wait > Duration::from_millis(policy.max_backoff_ms) => None,
+                        Some(wait) => {
+                            host_permit.defer(wait);
+                            Some(wait)
+                        }
+                        None => Some(policy.backoff(attempt)),
+                    }
+                } else {
+                    None
+                };
+                (Some(status.as_u16()), None, retry_delay)
+            }
+            Err(e) => {
+                let retry_delay = (e.is_connect() || e.is_timeout()).then(|| policy.backoff(attempt));
+                (None, Some(e.to_string()), retry_delay)
+            }
+        };
+
+        // Only retry while attempts remain and the wait fits in the time budget
+        let retry_delay = retry_delay.filter(|wait| {
+            attempt <= policy.max_retries
+                && deadline.is_none_or(|deadline| Instant::now() + *wait < deadline)
+        });
+
+        attempts.push(FetchAttempt {
+            url: url.to_string(),
+            attempt,
+            status,
+            error,
+            duration_ms,
+            retry_delay_ms: retry_delay.map(|wait| wait.as_millis() as u64),
+        });
+
+        match retry_delay {
+            Some(wait) => {
+                drop(host_permit);
+                tokio::time::sleep(wait).await;
+            }
+            None => return sent.map(|response| (response, host_permit)).map_err(CrawlerError::from),
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+use super::normalize::{FoldedText, TextNormalizer};
+use super::CrawlerError;
+
+// How a keyword is compared with page text
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchMode {
+    #[default]
+    Substring,     // Case-insensitive, anywhere in the text ("ai" also hits "said")
+    WholeWord,     // Case-insensitive, only at word boundaries
+    CaseSensitive, // Exact case, anywhere in the text; not normalized
+    Regex,         // Regular expression, case-sensitive unless it starts with (?i); not normalized
+}
+
+// A keyword is either a plain string or `{"term": "...", "mode": "whole_word"}`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Keyword {
+    Plain(String),
+    WithMode {
+        term: String,
+        mode: Option<MatchMode>, // Falls back to the request's keyword_mode
+    },
+}
+
+impl Keyword {
+    pub fn term(&self) -> &str {
+        match self {
+            Keyword::Plain(term) | Keyword::WithMode { term, .. } => term,
+        }
+    }
+
+    fn mode(&self) -> Option<MatchMode> {
+        match self {
+            Keyword::Plain(_) => None,
+            Keyword::WithMode { mode, .. } => *mode,
+        }
+    }
+}
+
+// A keyword compiled for its match mode
+#[derive(Debug)]
+pub enum KeywordMatcher {
+    Folded(String),       // Case-folded substring
+    FoldedPattern(Regex), // Pattern run on the case-folded text
+    Exact(String),        // Substring with exact case
+    Pattern(Regex),       // User regex run on the original text
+}
+
+impl KeywordMatcher {
+    pub fn compile(keyword: &Keyword, default_mode: MatchMode, normalizer: &TextNormalizer) -> Result<Self, CrawlerError> {
+        let term = keyword.term().trim();
+        if term.is_empty() {
+            return Err(CrawlerError::KeywordError("keywords must not be empty".to_string()));
+        }
+
+        let build = |pattern: &str| {
+            RegexBuilder::new(pattern)
+                .size_limit(1 << 20)
+                .build()
+                .map_err(|e| CrawlerError::KeywordError(format!("{}: {}", term, e)))
+        };
+        Ok(match keyword.mode().unwrap_or(default_mode) {
+            MatchMode::Substring => KeywordMatcher::Folded(normalizer.normalize(term)),
+            MatchMode::WholeWord => {
+                // \b only holds next to a word character, so a term such as
+                // "C++" or ".NET" only gets one on its word-character side
+                let folded = normalizer.normalize(term);
+                let boundary = |edge: Option<char>| if edge.is_some_and(is_word_char) { r"\b" } else { "" };
+                let pattern = format!(
+                    "{}{}{}",
+                    boundary(folded.chars().next()),
+                    regex::escape(&folded),
+                    boundary(folded.chars().next_back())
+                );
+                KeywordMatcher::FoldedPattern(build(&pattern)?)
+            }
+            MatchMode::CaseSensitive => KeywordMatcher::Exact(term.to_string()),
+            MatchMode::Regex => KeywordMatcher::Pattern(build(term)?),
+        })
+    }
+
+    // Byte ranges of every occurrence in the original text
+    pub fn find(&self, folded_text: &FoldedText) -> Vec<(usize, usize)> {
+        let original = folded_text.original();
+        match self {
+            KeywordMatcher::Folded(folded_keyword) => folded_text
+                .folded
+                .match_indices(folded_keyword.as_str())
+                .map(|(i, found)| folded_text.original_range(i, i + found.len()))
+                .collect(),
+            KeywordMatcher::FoldedPattern(pattern) => pattern
+                .find_iter(&folded_text.folded)
+                .map(|found| folded_text.original_range(found.start(), found.end()))
+                .collect(),
+            KeywordMatcher::Exact(term) => original
+                .match_indices(term.as_str())
+                .map(|(i, found)| (i, i + found.len()))
+                .collect(),
+            // Empty matches (e.g. from `a*`) carry no text to report
+            KeywordMatcher::Pattern(pattern) => pattern
+                .find_iter(original)
+                .filter(|found| !found.is_empty())
+                .map(|found| (found.start(), found.end()))
+                .collect(),
+        }
+    }
+}
+
+// Helper function to tell the characters regex's \b treats as part of a word
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find(term: &str, mode: MatchMode, text: &str) -> Vec<String> {
+        let normalizer = TextNormalizer::new(None, None, None);
+        let keyword = Keyword::WithMode {
+            term: term.to_string(),
+            mode: Some(mode),
+        };
+        let matcher = KeywordMatcher::compile(&keyword, MatchMode::Substring, &normalizer).unwrap();
+        matcher
+            .find(&normalizer.fold(text))
+            .into_iter()
+            .map(|(start, end)| text[start..end].to_string())
+            .collect()
+    }
+
+    #[test]
+    fn whole_word_skips_matches_inside_words() {
+        assert!(find("AI", MatchMode::WholeWord, "He said so").is_empty());
+        assert_eq!(find("AI", MatchMode::WholeWord, "He said AI, not ai_x or (ai)"), vec!["AI", "ai"]);
+        // Substring mode still finds it inside words
+        assert_eq!(find("AI", MatchMode::Substring, "He said so"), vec!["ai"]);
+    }
+
+    #[test]
+    fn whole_word_terms_with_symbol_edges() {
+        assert_eq!(find("C++", MatchMode::WholeWord, "Written in C++ and C"), vec!["C++"]);
+        assert_eq!(find("C++", MatchMode::WholeWord, "c++17 is out"), vec!["c++"]);
+        assert!(find("C++", MatchMode::WholeWord, "ABC++ parser").is_empty());
+        assert_eq!(find(".NET", MatchMode::WholeWord, "Uses .NET and ASP.NET"), vec![".NET", ".NET"]);
+        assert!(find(".NET", MatchMode::WholeWord, "See example.network").is_empty());
+    }
+
+    #[test]
+    fn case_sensitive_and_regex_modes() {
+        assert_eq!(find("AI", MatchMode::CaseSensitive, "AI and ai"), vec!["AI"]);
+        assert_eq!(find(r"bank\w*", MatchMode::Regex, "Bank banking banks"), vec!["banking", "banks"]);
+        assert_eq!(find(r"(?i)bank", MatchMode::Regex, "Bank"), vec!["Bank"]);
+        // Empty regex matches are not reported
+        assert!(find("x*", MatchMode::Regex, "abc").is_empty());
+    }
+
+    #[test]
+    fn invalid_keywords_are_rejected() {
+        let normalizer = TextNormalizer::new(None, None, None);
+        let regex = Keyword::WithMode {
+            term: "(unclosed".to_string(),
+            mode: Some(MatchMode::Regex),
+        };
+        assert!(matches!(
+            KeywordMatcher::compile(&regex, MatchMode::Substring, &normalizer),
+            Err(CrawlerError::KeywordError(_))
+        ));
+        assert!(matches!(
+            KeywordMatcher::compile(&Keyword::Plain("  ".to_string()), MatchMode::Substring, &normalizer),
+            Err(CrawlerError::KeywordError(_))
+        ));
+        // The default mode applies to plain keywords
+        assert!(matches!(
+            KeywordMatcher::compile(&Keyword::Plain("[".to_string()), MatchMode::Regex, &normalizer),
+            Err(CrawlerError::KeywordError(_))
+        ));
+    }
+}
@@ -1,14 +1,75 @@
+use reqwest::header::LAST_MODIFIED;
 use reqwest::Client;
 use scraper::{Html, Selector};
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use futures::future::join_all;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use thiserror::Error;
+use tokio::sync::Semaphore;
 use url::Url;
 use regex::Regex;
-use chrono::{DateTime, NaiveDate};
+use sha2::{Digest, Sha256};
+use chrono::NaiveDate;
 use html2text;
 
+mod blocks;
+mod canonical;
+mod dates;
+mod extract;
+mod feeds;
+mod fetch;
+mod keyword;
+mod normalize;
+mod pagination;
+mod politeness;
+mod query;
+mod readability;
+mod robots;
+mod scope;
+mod scoring;
+mod sitemap;
+mod snippet;
+mod structured;
+mod tables;
+mod text;
+use canonical::UrlCanonicalizer;
+use dates::DateKind;
+use extract::Extractor;
+use keyword::KeywordMatcher;
+use query::{Query, WordIndex};
+use robots::RobotsCache;
+use scope::UrlFilter;
+use scoring::PageTerms;
+use normalize::TextNormalizer;
+use text::PageText;
+
+pub use blocks::{ContentBlock, ContentFormat};
+pub use dates::PageDate;
+pub use extract::{ExtractedValue, ExtractionRule};
+pub use fetch::{FetchAttempt, RedirectHop, RetryPolicy};
+pub use keyword::{Keyword, MatchMode};
+pub use pagination::PaginationStrategy;
+pub use scope::ScopePolicy;
+pub use scoring::{RankedPage, ScoringOptions};
+pub use normalize::NormalizationOptions;
+pub use snippet::SnippetOptions;
+pub use structured::StructuredData;
+pub use tables::DataTable;
+pub use text::{MatchField, TextSource};
+
+// User agent sent with every crawler request
+const CRAWLER_USER_AGENT: &str = "rust-crawler/0.1";
+
+// Per-request limits, so a stalled server cannot hang a crawl; the total
+// covers connecting, sending and reading the body
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Default limits for concurrent domain crawls
+const DEFAULT_MAX_CONCURRENCY: usize = 4;
+const DEFAULT_MAX_CONCURRENCY_PER_HOST: usize = 1;
+
 // Helper function to parse date string to NaiveDate
 fn parse_date_string(date_str: &str) -> Result<NaiveDate, CrawlerError> {
     NaiveDate::parse_from_str(date_str, "%Y-%m-%d")
@@ -41,100 +102,35 @@ fn validate_date_range(date_from: Option<&String>, date_to: Option<&String>) ->
     Ok((from_date, to_date))
 }
 
-// Helper function to extract date from HTML meta tags or structured data
-fn extract_page_dates(document: &Html) -> (Option<String>, Option<String>) {
-    let mut last_modified = None;
-    let mut published_date = None;
-    
-    // Try to extract from meta tags
-    if let Ok(meta_selector) = Selector::parse("meta") {
-        for meta in document.select(&meta_selector) {
-            if let Some(property) = meta.value().attr("property") {
-                match property {
-                    "article:modified_time" | "article:updated_time" => {
-                        if let Some(content) = meta.value().attr("content") {
-                            last_modified = Some(content.to_string());
-                        }
-                    }
-                    "article:published_time" => {
-                        if let Some(content) = meta.value().attr("content") {
-                            published_date = Some(content.to_string());
-                        }
-                    }
-                    _ => {}
-                }
-            }
-            
-            if let Some(name) = meta.value().attr("name") {
-                match name {
-                    "last-modified" | "date-modified" => {
-                        if let Some(content) = meta.value().attr("content") {
-                            last_modified = Some(content.to_string());
-                        }
-                    }
-                    "date" | "publish-date" | "publication-date" => {
-                        if let Some(content) = meta.value().attr("content") {
-                            published_date = Some(content.to_string());
-                        }
-                    }
-                    _ => {}
-                }
-            }
-        }
-    }
-    
-    // Try to extract from time elements with datetime attribute
-    if let Ok(time_selector) = Selector::parse("time[datetime]") {
-        for time_elem in document.select(&time_selector) {
-            if let Some(datetime) = time_elem.value().attr("datetime") {
-                if published_date.is_none() {
-                    published_date = Some(datetime.to_string());
-                }
-            }
-        }
-    }
-    
-    (last_modified, published_date)
+// Helper function to check a single date against the requested range
+fn date_in_range(date: NaiveDate, date_from: Option<NaiveDate>, date_to: Option<NaiveDate>) -> bool {
+    let after_from = date_from.is_none_or(|from| date >= from);
+    let before_to = date_to.is_none_or(|to| date <= to);
+    after_from && before_to
 }
 
 // Helper function to check if a page matches the date filter
 fn matches_date_filter(
-    last_modified: &Option<String>,
-    published_date: &Option<String>,
+    page_dates: &[PageDate],
     date_from: Option<NaiveDate>,
     date_to: Option<NaiveDate>,
+    exclude_undated: bool,
 ) -> bool {
-    // If no date filter is specified, include all pages
-    if date_from.is_none() && date_to.is_none() {
-        return true;
-    }
+    // Use every date the page declares, whatever its source
+    let page_dates = page_dates.iter().filter_map(|page_date| page_date.date).collect::<Vec<_>>();
     
-    // Try to parse dates from the page
-    let page_dates = [last_modified, published_date]
-        .iter()
-        .filter_map(|date_opt| {
-            date_opt.as_ref().and_then(|date_str| {
-                // Try different date formats
-                DateTime::parse_from_rfc3339(date_str)
-                    .map(|dt| dt.date_naive())
-                    .or_else(|_| NaiveDate::parse_from_str(date_str, "%Y-%m-%d"))
-                    .or_else(|_| NaiveDate::parse_from_str(date_str, "%Y/%m/%d"))
-                    .ok()
-            })
-        })
-        .collect::<Vec<_>>();
-    
-    // If we couldn't parse any dates from the page, include it by default
+    // If we couldn't parse any dates from the page, include it unless asked not to
     if page_dates.is_empty() {
+        return !exclude_undated;
+    }
+    
+    // If no date filter is specified, include all dated pages
+    if date_from.is_none() && date_to.is_none() {
         return true;
     }
     
     // Check if any of the page dates fall within the specified range
-    page_dates.iter().any(|page_date| {
-        let after_from = date_from.map_or(true, |from| *page_date >= from);
-        let before_to = date_to.map_or(true, |to| *page_date <= to);
-        after_from && before_to
-    })
+    page_dates.iter().any(|page_date| date_in_range(*page_date, date_from, date_to))
 }
 
 // Add text cleaning functions
@@ -157,32 +153,6 @@ fn clean_html_text(html_text: &str) -> String {
     normalized_text.trim().to_string()
 }
 
-fn calculate_relevance_score(keyword: &str, context: &str) -> f32 {
-    // Simple relevance scoring based on keyword density
-    let context_lower = context.to_lowercase();
-    let keyword_lower = keyword.to_lowercase();
-    
-    // Count occurrences
-    let count = context_lower.matches(&keyword_lower).count();
-    if count == 0 {
-        return 0.0;
-    }
-    
-    // Calculate density (occurrences per 100 characters)
-    let density = (count as f32 * 100.0) / context.len() as f32;
-    
-    // Check if keyword appears in the first third of the context (higher relevance)
-    let first_third_len = context.len() / 3;
-    let first_third = &context_lower[..first_third_len.min(context_lower.len())];
-    let position_boost = if first_third.contains(&keyword_lower) { 0.3 } else { 0.0 };
-    
-    // Combine factors (density has more weight)
-    let score = (density * 0.7) + position_boost;
-    
-    // Normalize to 0-1 range (capping at 1.0)
-    (score * 10.0).min(100.0)
-}
-
 #[derive(Error, Debug)]
 pub enum CrawlerError {
     #[error("Request error: {0}")]
@@ -200,6 +170,39 @@ pub enum CrawlerError {
     #[error("Date parsing error: {0}")]
     DateParsingError(String),
     
+    #[error("HTTP {status} returned by {url}")]
+    HttpStatus { status: u16, url: String },
+    
+    #[error("Too many redirects while fetching {0}")]
+    TooManyRedirects(String),
+    
+    #[error("Disallowed by robots.txt: {0}")]
+    RobotsDisallowed(String),
+    
+    #[error("robots.txt unreachable: {0}")]
+    RobotsUnavailable(String),
+    
+    #[error("Redirect target skipped ({reason:?}): {url}")]
+    RedirectSkipped { url: String, reason: SkipReason },
+    
+    #[error("Feed error: {0}")]
+    FeedError(String),
+    
+    #[error("Invalid URL pattern: {0}")]
+    InvalidPattern(String),
+    
+    #[error("Invalid query: {0}")]
+    QueryError(String),
+    
+    #[error("Invalid keyword: {0}")]
+    KeywordError(String),
+    
+    #[error("Invalid pagination: {0}")]
+    PaginationError(String),
+    
+    #[error("Invalid scoring options: {0}")]
+    ScoringError(String),
+    
     #[error("Other error: {0}")]
     Other(String),
 }
@@ -210,49 +213,130 @@ pub struct CrawlResult {
     pub total_pages_crawled: usize,
     pub total_processing_time_ms: u64,
     pub crawl_timestamp: String,
+    pub ranked_pages: Vec<RankedPage>, // Keyword-matched pages of all domains, best BM25 score first
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DomainResult {
     pub url: String,
-    pub title: Option<String>,
-    pub content: String, // Full cleaned text content of the page
-    pub matches: Vec<KeywordMatch>,
-    pub pages_crawled: usize,
+    pub title: Option<String>, // Title of the first matched page
+    pub content: String, // Cleaned text of all matched pages, separated by "--- Next Page ---"
+    pub matches: Vec<KeywordMatch>, // Matches of all pages
+    pub pages_crawled: usize, // Pages that returned a response
     pub has_more_pages: bool,
     pub metadata: Option<CrawlMetadata>,
+    pub pages: Vec<PageResult>, // Every fetched URL with its own results; the fields above summarize them
+    pub skipped_urls: Vec<SkippedUrl>, // URLs that were discovered but not fetched
     pub error: Option<String>, // To capture domain-specific errors
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PageResult {
+    pub url: String,
+    pub depth: usize, // Number of link hops from the seed URL (pagination does not add depth)
+    pub status: Option<u16>, // HTTP status of the final response
+    pub final_url: Option<String>, // URL after following redirects
+    pub redirect_chain: Vec<RedirectHop>, // Redirect responses between `url` and `final_url`
+    pub attempts: Vec<FetchAttempt>, // Every fetch attempt, including retries
+    pub fetched_at: String, // Unix timestamp (seconds) when the fetch started
+    pub fetch_duration_ms: u64, // Time to fetch the page, including retries, redirects and the body
+    pub title: Option<String>,
+    pub published_date: Option<String>, // Preferred publication date, as found
+    pub last_modified: Option<String>, // Preferred modification date, as found
+    pub dates: Vec<PageDate>, // Every date found for the page, tagged with its source
+    pub structured_data: Option<StructuredData>, // JSON-LD, microdata, OpenGraph and Twitter Card metadata
+    pub extracted: BTreeMap<String, ExtractedValue>, // Values of the request's extraction rules, by rule name
+    pub tables: Vec<DataTable>, // Data tables of the page (layout tables are skipped)
+    pub content: Option<String>, // Cleaned text or Markdown (see content_format); only for pages that were keyword-matched
+    pub blocks: Option<Vec<ContentBlock>>, // Block tree of the content, with content_format "blocks"
+    pub content_hash: Option<String>, // SHA-256 (hex) of `content`
+    pub matches: Vec<KeywordMatch>,
+    pub canonical_url: Option<String>, // From <link rel="canonical">, if present
+    pub duplicate_of: Option<String>, // Set when the canonical URL or redirect target was already crawled; the page is not matched
+    pub outside_date_range: bool, // Dated outside date_from/date_to, or undated with exclude_undated; the page is not matched
+    pub error: Option<String>, // Set when the page failed or returned a non-2xx status
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SkippedUrl {
+    pub url: String,
+    pub reason: SkipReason,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SkipReason {
+    RobotsDisallowed, // Blocked by the host's robots.txt
+    RobotsUnavailable, // The host's robots.txt could not be fetched (network error or 5xx), so nothing may be crawled
+    NotIncluded, // Matched none of the include_patterns
+    Excluded, // Matched one of the exclude_patterns
+    OutOfScope, // Reached by a redirect to a host outside the crawl scope
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CrawlMetadata {
     pub crawl_timestamp: String,
     pub total_processing_time_ms: u64,
     pub content_summary: Option<String>,
-    pub last_modified: Option<String>, // ISO 8601 date string for page last modified date
-    pub published_date: Option<String>, // ISO 8601 date string for page published date
+    pub last_modified: Option<String>, // Most recent modification date of the crawled pages (YYYY-MM-DD)
+    pub published_date: Option<String>, // Most recent publication date of the crawled pages (YYYY-MM-DD)
+    pub earliest_date: Option<NaiveDate>, // Earliest date of any kind seen on the crawled pages
+    pub latest_date: Option<NaiveDate>, // Latest date of any kind seen on the crawled pages
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct KeywordMatch {
     pub keyword: String,
-    pub context: String,
-    pub cleaned_text: String,
+    pub field: MatchField, // Page field the keyword was found in
+    pub context: String, // Text snippets around each occurrence, separated by "\n...\n"
+    pub cleaned_text: String, // First snippet on a single line
     pub count: usize,
-    pub relevance_score: Option<f32>,
+    pub relevance_score: Option<f32>, // BM25 score of the page for this keyword, 0-100 relative to the crawl's best page
     pub source_url: String, // URL where this keyword match was found
+    pub matched_clauses: Vec<String>, // For `query` matches: the terms, phrases and NEAR clauses that fired
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CrawlRequest {
     pub url: String, // Can contain multiple URLs separated by commas
-    pub keywords: Vec<String>,
+    pub keywords: Vec<Keyword>, // Plain strings or {"term": "...", "mode": "whole_word"}
+    pub keyword_mode: Option<MatchMode>, // Default match mode for keywords (default: substring)
+    pub query: Option<String>, // Boolean query, e.g. `("data breach" OR leak) AND bank NOT "press release"`
     pub max_depth: Option<usize>,
     pub max_time_seconds: Option<u64>,
-    pub follow_pagination: Option<bool>,
+    pub follow_pagination: Option<bool>, // Default: true when `pagination` is given
     pub max_pages: Option<usize>,
     pub date_from: Option<String>, // ISO 8601 date string (YYYY-MM-DD)
     pub date_to: Option<String>,   // ISO 8601 date string (YYYY-MM-DD)
+    pub exclude_undated: Option<bool>, // Leave out pages without any recognizable date (default: include them)
+    pub max_concurrency: Option<usize>, // Domains crawled at the same time (default 4)
+    pub max_concurrency_per_host: Option<usize>, // Domains on the same host crawled at the same time (default 1)
+    pub retry: Option<RetryPolicy>, // Retry/backoff settings for page fetches
+    pub use_sitemaps: Option<bool>, // Seed the frontier from the site's sitemaps
+    pub mode: Option<CrawlMode>, // What to crawl from each seed (default: pages)
+    pub pagination: Option<PaginationStrategy>, // Custom next-page strategy (default: built-in heuristics)
+    pub tracking_params: Option<Vec<String>>, // Query parameters stripped from URLs (`utm_*` style prefixes allowed); replaces the default list
+    pub scope: Option<ScopePolicy>, // Hosts that discovered links may lead to (default: same_host)
+    pub allowed_hosts: Option<Vec<String>>, // Hosts for the allowlist scope; `*.example.com` also allows subdomains
+    pub include_patterns: Option<Vec<String>>, // Only crawl discovered URLs matching one of these (globs, or regexes with a `re:` prefix)
+    pub exclude_patterns: Option<Vec<String>>, // Never crawl discovered URLs matching one of these
+    pub match_fields: Option<Vec<MatchField>>, // Page fields keywords are matched against (default: body)
+    pub text_source: Option<TextSource>, // Body text used for matching and `content` (default: full_page)
+    pub content_format: Option<ContentFormat>, // Representation of `content`: text (default), markdown or blocks
+    pub tables_csv: Option<bool>, // Also export each data table as CSV (default: false)
+    pub snippets: Option<SnippetOptions>, // Snippet window, count and sentence snapping
+    pub language: Option<String>, // BCP 47 tag of the pages' language (e.g. "id", "tr"), or "auto" to detect it per page
+    pub normalization: Option<NormalizationOptions>, // NFKC, diacritic stripping and stemming for keywords and page text
+    pub scoring: Option<ScoringOptions>, // BM25 parameters and title/body weights
+    pub extract: Option<Vec<ExtractionRule>>, // Named CSS extraction rules run against every page
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CrawlMode {
+    #[default]
+    Pages, // Crawl the seed page, its pagination and (with max_depth) its links
+    Feeds, // Crawl the entries of the seed's RSS/Atom feeds
 }
 
 // Helper function to parse multiple URLs from comma-separated string
@@ -296,20 +380,78 @@ pub async fn crawl_website(request: &CrawlRequest) -> Result<CrawlResult, Crawle
     // Validate date range if provided
     let (date_from, date_to) = validate_date_range(request.date_from.as_ref(), request.date_to.as_ref())?;
     
+    if let Some(pagination) = &request.pagination {
+        // A pagination strategy turns pagination on unless it is switched off
+        if request.follow_pagination == Some(false) {
+            return Err(CrawlerError::PaginationError("pagination is set but follow_pagination is false".to_string()));
+        }
+        pagination.validate()?;
+    }
+    
+    if let Some(scoring) = &request.scoring {
+        scoring.validate()?;
+    }
+    
+    // Reject bad keywords and queries before anything is fetched
+    let normalizer = TextNormalizer::new(request.language.as_deref(), request.normalization.as_ref(), None);
+    if let Some(query) = &request.query {
+        Query::parse(query, &normalizer)?;
+    }
+    compile_keywords(request, &normalizer)?;
+    Extractor::compile(request.extract.as_deref().unwrap_or_default())?;
+    
+    // Scope and URL patterns apply to every discovered link of every domain
+    let url_filter = UrlFilter::new(
+        request.scope.unwrap_or_default(),
+        request.allowed_hosts.as_deref().unwrap_or_default(),
+        request.include_patterns.as_deref().unwrap_or_default(),
+        request.exclude_patterns.as_deref().unwrap_or_default(),
+    )?;
+    
     // Parse multiple URLs from the comma-separated string
     let urls = parse_urls(&request.url)?;
     
     let mut domain_results = Vec::new();
     let mut total_pages_crawled = 0;
     
+    // robots.txt is fetched once per host and shared by all domains
+    let robots_cache = RobotsCache::new();
+    
+    // Limit how many domains are crawled at once, overall and per host
+    let global_limit = Semaphore::new(request.max_concurrency.unwrap_or(DEFAULT_MAX_CONCURRENCY).max(1));
+    let per_host_limit = request.max_concurrency_per_host.unwrap_or(DEFAULT_MAX_CONCURRENCY_PER_HOST).max(1);
+    let mut host_limits: HashMap<&str, Semaphore> = HashMap::new();
+    for base_url in &urls {
+        host_limits
+            .entry(base_url.host_str().unwrap_or(""))
+            .or_insert_with(|| Semaphore::new(per_host_limit));
+    }
+    
+    // Crawl all domains concurrently; join_all keeps results in input order
+    let crawls = urls.iter().map(|base_url| {
+        let host_limit = &host_limits[base_url.host_str().unwrap_or("")];
+        let global_limit = &global_limit;
+        let robots_cache = &robots_cache;
+        let url_filter = &url_filter;
+        async move {
+            // Take the host permit first so a queued host does not hold a global slot
+            let _host_permit = host_limit.acquire().await;
+            let _global_permit = global_limit.acquire().await;
+            crawl_single_domain(base_url, request, start_processing_time, date_from, date_to, robots_cache, url_filter).await
+        }
+    });
+    let domain_outcomes = join_all(crawls).await;
+    
+    // Term counts of every keyword-matched page, for scoring across domains
+    let mut page_terms = Vec::new();
+    
     // Process each domain
-    for base_url in urls {
-        let domain_result = crawl_single_domain(&base_url, request, start_processing_time, date_from, date_to).await;
-        
+    for (base_url, domain_result) in urls.iter().zip(domain_outcomes) {
         match domain_result {
-            Ok(result) => {
+            Ok((result, terms)) => {
                 total_pages_crawled += result.pages_crawled;
                 domain_results.push(result);
+                page_terms.extend(terms);
             }
             Err(err) => {
                 // Create an error result for this domain
@@ -321,6 +463,8 @@ pub async fn crawl_website(request: &CrawlRequest) -> Result<CrawlResult, Crawle
                     pages_crawled: 0,
                     has_more_pages: false,
                     metadata: None,
+                    pages: Vec::new(),
+                    skipped_urls: Vec::new(),
                     error: Some(err.to_string()),
                 };
                 domain_results.push(error_result);
@@ -328,6 +472,22 @@ pub async fn crawl_website(request: &CrawlRequest) -> Result<CrawlResult, Crawle
         }
     }
     
+    // Rank pages with BM25 over the whole crawl and attach the per-keyword
+    // scores to the matches
+    let mut scored_terms: Vec<String> = request.keywords.iter().map(|keyword| keyword.term().to_string()).collect();
+    scored_terms.extend(request.query.clone());
+    let ranked_pages = scoring::rank_pages(&scored_terms, &page_terms, &request.scoring.clone().unwrap_or_default());
+    let keyword_scores = scoring::keyword_scores(&ranked_pages);
+    let all_matches = domain_results.iter_mut().flat_map(|result| {
+        let page_matches = result.pages.iter_mut().flat_map(|page| page.matches.iter_mut());
+        result.matches.iter_mut().chain(page_matches)
+    });
+    for keyword_match in all_matches {
+        keyword_match.relevance_score = keyword_scores
+            .get(&(keyword_match.source_url.as_str(), keyword_match.keyword.as_str()))
+            .copied();
+    }
+    
     // Create metadata
     let now = SystemTime::now();
     let timestamp = now.duration_since(UNIX_EPOCH)
@@ -339,6 +499,7 @@ pub async fn crawl_website(request: &CrawlRequest) -> Result<CrawlResult, Crawle
         total_pages_crawled,
         total_processing_time_ms: start_processing_time.elapsed().as_millis() as u64,
         crawl_timestamp: format!("{}", timestamp),
+        ranked_pages,
     })
 }
 
@@ -348,29 +509,89 @@ async fn crawl_single_domain(
     start_processing_time: Instant,
     date_from: Option<NaiveDate>,
     date_to: Option<NaiveDate>,
-) -> Result<DomainResult, CrawlerError> {
-    let client = Client::new();
+    robots_cache: &RobotsCache,
+    url_filter: &UrlFilter,
+) -> Result<(DomainResult, Vec<PageTerms>), CrawlerError> {
+    // Redirects are followed by fetch_page so each hop is checked and recorded
+    let client = Client::builder()
+        .user_agent(CRAWLER_USER_AGENT)
+        .redirect(reqwest::redirect::Policy::none())
+        .timeout(REQUEST_TIMEOUT)
+        .connect_timeout(CONNECT_TIMEOUT)
+        .build()?;
     
-    // Set up time tracking if max_time_seconds is specified
-    let start_time = Instant::now();
+    // Set up time tracking if max_time_seconds is specified; the budget is
+    // shared by all domains of the request, so it starts with the request
+    let start_time = start_processing_time;
     let time_limit = request.max_time_seconds.map(Duration::from_secs);
+    let deadline = time_limit.map(|limit| tokio::time::Instant::from_std(start_time + limit));
     
-    // Track visited URLs to avoid duplicates
+    // Track visited URLs (by canonical key) to avoid duplicates
+    let canonicalizer = UrlCanonicalizer::new(request.tracking_params.as_deref());
     let mut visited_urls = HashSet::new();
-    visited_urls.insert(base_url.to_string());
+    visited_urls.insert(canonicalizer.key(base_url));
+    
+    // Canonical keys of pages that were actually fetched and parsed
+    let mut crawled_keys = HashSet::new();
     
     // Initialize result
-    let mut all_matches = Vec::new();
     let mut pages_crawled = 0;
     let mut has_more_pages = false;
-    let mut current_url = base_url.clone();
-    let mut page_title = None;
-    let mut full_content = String::new(); // Store all page content
+    let mut pages = Vec::new();
+    let mut skipped_urls = Vec::new();
+    let mut page_terms = Vec::new();
     
     // Set max pages to crawl
     let max_pages = request.max_pages.unwrap_or(10);
+    // Retry settings come from the request, so they are capped
+    let retry_policy = &request.retry.clone().unwrap_or_default().clamped();
+    
+    // Without max_depth only the seed (and its pagination chain) is crawled
+    let max_depth = request.max_depth.unwrap_or(0);
+    let follow_pagination = request.follow_pagination.unwrap_or(request.pagination.is_some());
+    let exclude_undated = request.exclude_undated.unwrap_or(false);
+    let match_fields = request.match_fields.clone().unwrap_or_else(|| vec![MatchField::Body]);
+    let text_source = request.text_source.unwrap_or_default();
+    let content_format = request.content_format.unwrap_or_default();
+    let tables_csv = request.tables_csv.unwrap_or(false);
+    let extractor = Extractor::compile(request.extract.as_deref().unwrap_or_default())?;
+    
+    // Breadth-first frontier of (url, depth) pairs
+    let mut frontier = VecDeque::new();
+    
+    // Position of each page in the seed's pagination chain (seed = 0)
+    let mut pagination_index: HashMap<String, usize> = HashMap::new();
+    pagination_index.insert(base_url.to_string(), 0);
     
-    loop {
+    // Publication dates from feed entries, used when a page has none of its own
+    let mut feed_dates: HashMap<String, String> = HashMap::new();
+    
+    if request.mode.unwrap_or_default() == CrawlMode::Feeds {
+        // Feed mode crawls the entries of the seed's feeds instead of the seed page
+        let entries = feeds::collect_entries(&client, base_url, retry_policy, robots_cache, deadline, date_from, date_to).await?;
+        for entry in entries {
+            if let Some(entry_url) = admit_url(&entry.url, base_url, url_filter, &canonicalizer, &mut visited_urls, &mut skipped_urls) {
+                if let Some(published) = entry.published {
+                    feed_dates.insert(entry_url.to_string(), published);
+                }
+                frontier.push_back((entry_url, 1));
+            }
+        }
+    } else {
+        frontier.push_back((base_url.clone(), 0));
+    }
+    
+    // Sitemap URLs are additional seeds at depth 0
+    if request.use_sitemaps.unwrap_or(false) {
+        let sitemap_urls = sitemap::discover_urls(&client, base_url, retry_policy, robots_cache, deadline, date_from, date_to).await;
+        for url in sitemap_urls {
+            if let Some(url) = admit_url(&url, base_url, url_filter, &canonicalizer, &mut visited_urls, &mut skipped_urls) {
+                frontier.push_back((url, 0));
+            }
+        }
+    }
+    
+    while let Some((current_url, depth)) = frontier.pop_front() {
         // Check if we've exceeded the time limit
         if let Some(limit) = time_limit {
             if start_time.elapsed() > limit {
@@ -385,225 +606,467 @@ async fn crawl_single_domain(
             break;
         }
         
-        // Fetch the webpage content
-        let response = client.get(current_url.clone()).send().await?;
-        let html_content = response.text().await?;
+        // Fetch the webpage content; robots.txt is checked for every hop and
+        // transient failures are retried
+        let fetched_at = unix_timestamp();
+        let fetch_started = Instant::now();
+        // The seed's own redirects are always followed; every other page must
+        // stay in scope through its redirects
+        let redirect_scope = (current_url != *base_url).then_some((url_filter, base_url));
+        let fetch = fetch::fetch_page(&client, &current_url, retry_policy, robots_cache, redirect_scope, deadline).await;
+        let final_url = fetch.final_url;
+        let host_permit = fetch.host_permit;
+        let mut page = PageResult {
+            url: current_url.to_string(),
+            depth,
+            status: None,
+            final_url: Some(final_url.to_string()),
+            redirect_chain: fetch.redirect_chain,
+            attempts: fetch.attempts,
+            fetched_at,
+            fetch_duration_ms: fetch_started.elapsed().as_millis() as u64,
+            title: None,
+            published_date: None,
+            last_modified: None,
+            dates: Vec::new(),
+            structured_data: None,
+            extracted: BTreeMap::new(),
+            tables: Vec::new(),
+            content: None,
+            blocks: None,
+            content_hash: None,
+            matches: Vec::new(),
+            canonical_url: None,
+            duplicate_of: None,
+            outside_date_range: false,
+            error: None,
+        };
         
-        // Parse the HTML
-        let document = Html::parse_document(&html_content);
+        let response = match fetch.result {
+            Ok(response) => response,
+            Err(CrawlerError::TimeoutError) => {
+                has_more_pages = true;
+                break;
+            }
+            Err(CrawlerError::RobotsDisallowed(url)) => {
+                skipped_urls.push(SkippedUrl {
+                    url,
+                    reason: SkipReason::RobotsDisallowed,
+                });
+                continue;
+            }
+            Err(CrawlerError::RobotsUnavailable(url)) => {
+                skipped_urls.push(SkippedUrl {
+                    url,
+                    reason: SkipReason::RobotsUnavailable,
+                });
+                continue;
+            }
+            Err(CrawlerError::RedirectSkipped { url, reason }) => {
+                skipped_urls.push(SkippedUrl { url, reason });
+                continue;
+            }
+            // A seed that cannot be fetched fails the whole domain
+            Err(err) if current_url == *base_url => return Err(err),
+            Err(err) => {
+                page.error = Some(err.to_string());
+                pages.push(page);
+                continue;
+            }
+        };
         
-        // Extract page dates for filtering
-        let (page_last_modified, page_published_date) = extract_page_dates(&document);
+        // Redirect targets count as visited too
+        visited_urls.insert(canonicalizer.key(&final_url));
         
-        // Check if the page matches the date filter
-        if !matches_date_filter(&page_last_modified, &page_published_date, date_from, date_to) {
-            // Skip this page if it doesn't match the date filter
+        // Error pages are recorded but never parsed or keyword-matched
+        let status = response.status();
+        page.status = Some(status.as_u16());
+        if !status.is_success() {
+            let err = CrawlerError::HttpStatus {
+                status: status.as_u16(),
+                url: final_url.to_string(),
+            };
+            if current_url == *base_url {
+                return Err(err);
+            }
+            page.error = Some(err.to_string());
+            pages.push(page);
             pages_crawled += 1;
-            
-            // If pagination is not enabled, break after the first page
-            if !request.follow_pagination.unwrap_or(false) {
+            continue;
+        }
+        
+        // A redirect to a page that was already crawled is a duplicate; its
+        // links were queued when that page was crawled
+        if final_url != current_url && crawled_keys.contains(&canonicalizer.key(&final_url)) {
+            page.duplicate_of = Some(final_url.to_string());
+            pages.push(page);
+            pages_crawled += 1;
+            continue;
+        }
+        
+        // The Last-Modified header is one more source of the page's dates
+        let header_last_modified = response
+            .headers()
+            .get(LAST_MODIFIED)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        
+        let body = fetch::until_deadline(deadline, response.text()).await;
+        drop(host_permit);
+        page.fetch_duration_ms = fetch_started.elapsed().as_millis() as u64;
+        let html_content = match body {
+            Some(Ok(html_content)) => html_content,
+            // The time budget ran out while the body was still arriving
+            None => {
+                page.error = Some(CrawlerError::TimeoutError.to_string());
+                pages.push(page);
+                has_more_pages = true;
                 break;
             }
-            
-            // Try to find pagination links and continue
-            if let Some(next_url) = find_next_page_url(&document, &current_url) {
-                if !visited_urls.contains(&next_url.to_string()) {
-                    visited_urls.insert(next_url.to_string());
-                    current_url = next_url;
-                    continue;
+            Some(Err(err)) if current_url == *base_url => return Err(err.into()),
+            Some(Err(err)) => {
+                page.error = Some(CrawlerError::from(err).to_string());
+                pages.push(page);
+                pages_crawled += 1;
+                continue;
+            }
+        };
+        
+        // Parse the HTML
+        let document = Html::parse_document(&html_content);
+        
+        // Honor <link rel="canonical">: a page whose canonical URL was already
+        // crawled is a duplicate and is not matched again
+        let own_keys = [canonicalizer.key(&current_url), canonicalizer.key(&final_url)];
+        if let Some(canonical_url) = canonical::find_canonical_link(&document, &final_url) {
+            let canonical_key = canonicalizer.key(&canonical_url);
+            if !own_keys.contains(&canonical_key) {
+                if crawled_keys.contains(&canonical_key) {
+                    page.duplicate_of = Some(canonical_url.to_string());
                 } else {
-                    break;
+                    // Don't fetch the canonical URL again later
+                    visited_urls.insert(canonical_key.clone());
+                    crawled_keys.insert(canonical_key);
                 }
-            } else {
-                break;
             }
+            page.canonical_url = Some(canonical_url.to_string());
         }
+        crawled_keys.extend(own_keys);
         
-        // Extract title (only for the first page that matches the filter)
-        if all_matches.is_empty() {
-            let title_selector = Selector::parse("title").map_err(|e| CrawlerError::SelectorError(e.to_string()))?;
-            page_title = document.select(&title_selector).next().map(|element| element.inner_html());
+        // Queue the next page of a paginated listing at the same depth.
+        // Pagination is followed even from duplicates, because many sites
+        // point the canonical of every listing page at page 1.
+        if follow_pagination {
+            let page_index = pagination_index.get(current_url.as_str()).copied();
+            if let Some(next_url) = pagination::next_page_url(request.pagination.as_ref(), &document, &final_url, base_url, page_index) {
+                if let Some(next_url) = admit_url(&next_url, base_url, url_filter, &canonicalizer, &mut visited_urls, &mut skipped_urls) {
+                    if let Some(index) = page_index {
+                        pagination_index.insert(next_url.to_string(), index + 1);
+                    }
+                    frontier.push_back((next_url, depth));
+                }
+            }
         }
         
-        // Process the current page
-        let page_content = process_page_content(&html_content, &request.keywords, &mut all_matches, time_limit, start_time, &current_url)?;
-        
-        // Extract and accumulate full page content
-        let cleaned_page_content = clean_html_text(&html_content);
-        if !full_content.is_empty() {
-            full_content.push_str("\n\n--- Next Page ---\n\n");
+        // Queue in-scope outlinks one level deeper
+        if depth < max_depth {
+            for link in extract_links(&document, &final_url) {
+                if let Some(link) = admit_url(&link, base_url, url_filter, &canonicalizer, &mut visited_urls, &mut skipped_urls) {
+                    frontier.push_back((link, depth + 1));
+                }
+            }
         }
-        full_content.push_str(&cleaned_page_content);
         
         pages_crawled += 1;
+        if page.duplicate_of.is_some() {
+            pages.push(page);
+            continue;
+        }
         
-        // If pagination is not enabled, break after the first page
-        if !request.follow_pagination.unwrap_or(false) {
-            break;
+        // Extract page dates for filtering from the markup, the header, the
+        // feed entry, the URL and the page text. JSON-LD is parsed once for
+        // both the dates and the structured data.
+        let json_ld = structured::json_ld_blocks(&document);
+        let page_dates = dates::extract_page_dates(
+            &document,
+            &json_ld,
+            &final_url,
+            header_last_modified.as_deref(),
+            feed_dates.get(current_url.as_str()).map(String::as_str),
+        );
+        let main_content = match text_source {
+            TextSource::MainContent => readability::extract_main_content(&document),
+            TextSource::FullPage => None,
+        };
+        let page_text = PageText::extract(&document, main_content.as_ref());
+        page.title = page_text.title.clone();
+        page.structured_data = StructuredData::extract(&document, json_ld, &final_url);
+        page.extracted = extractor.extract(&document, &final_url);
+        page.tables = tables::extract_tables(&document, tables_csv);
+        
+        // Skip pages outside the date filter; their links were already queued
+        page.outside_date_range = !matches_date_filter(&page_dates, date_from, date_to, exclude_undated);
+        page.published_date = dates::preferred(&page_dates, DateKind::Published).map(|date| date.value.clone());
+        page.last_modified = dates::preferred(&page_dates, DateKind::Modified).map(|date| date.value.clone());
+        page.dates = page_dates;
+        if page.outside_date_range {
+            pages.push(page);
+            continue;
         }
         
-        // Try to find pagination links
-        if let Some(next_url) = find_next_page_url(&document, &current_url) {
-            if !visited_urls.contains(&next_url.to_string()) {
-                visited_urls.insert(next_url.to_string());
-                current_url = next_url;
-            } else {
-                // We've already visited this URL, so we're in a loop
+        // Process the current page; keywords are matched against its visible
+        // text (and the other requested fields), never against markup
+        match process_page_content(&page_text, request, &match_fields, &mut page.matches, time_limit, start_time, &current_url) {
+            Ok(terms) => page_terms.push(terms),
+            // Running out of time keeps the pages crawled so far
+            Err(CrawlerError::TimeoutError) => {
+                page.matches.clear();
+                pages.push(page);
+                has_more_pages = true;
                 break;
             }
-        } else {
-            // No more pagination links found
-            break;
+            Err(err) => return Err(err),
         }
+        
+        let content_document = main_content.as_ref().unwrap_or(&document);
+        let content = match content_format {
+            ContentFormat::Markdown => blocks::to_markdown(&blocks::extract_blocks(content_document, &final_url)),
+            ContentFormat::Text | ContentFormat::Blocks => match &main_content {
+                Some(main_content) => clean_html_text(&main_content.html()),
+                None => clean_html_text(&html_content),
+            },
+        };
+        if content_format == ContentFormat::Blocks {
+            page.blocks = Some(blocks::extract_blocks(content_document, &final_url));
+        }
+        page.content_hash = Some(content_hash(&content));
+        page.content = Some(content);
+        pages.push(page);
     }
     
+    // Domain-level fields summarize the matched pages
+    let matched_pages: Vec<&PageResult> = pages.iter().filter(|page| page.content.is_some()).collect();
+    let page_title = matched_pages.iter().find_map(|page| page.title.clone());
+    let full_content = matched_pages
+        .iter()
+        .filter_map(|page| page.content.as_deref())
+        .collect::<Vec<_>>()
+        .join("\n\n--- Next Page ---\n\n");
+    let all_matches: Vec<KeywordMatch> = matched_pages.iter().flat_map(|page| page.matches.iter().cloned()).collect();
+    
     // Create metadata
-    let now = SystemTime::now();
-    let timestamp = now.duration_since(UNIX_EPOCH)
-        .unwrap_or_else(|_| Duration::from_secs(0))
-        .as_secs();
+    let timestamp = unix_timestamp();
     
-    // Get the last extracted page dates (from the last processed page)
-    let (last_modified, published_date) = if !all_matches.is_empty() {
-        // If we have matches, we should have processed at least one page
-        // For now, we'll use None as we need to track dates per page
-        // TODO: Implement proper date tracking across multiple pages
-        (None, None)
-    } else {
-        (None, None)
+    // Date range of every page that was parsed, matched or not
+    let all_dates: Vec<&PageDate> = pages.iter().flat_map(|page| &page.dates).filter(|date| date.date.is_some()).collect();
+    let latest_of = |kind: DateKind| {
+        all_dates
+            .iter()
+            .filter(|date| date.kind == kind)
+            .filter_map(|date| date.date)
+            .max()
+            .map(|date| date.to_string())
     };
     
     let metadata = CrawlMetadata {
-        crawl_timestamp: format!("{}", timestamp),
+        crawl_timestamp: timestamp,
         total_processing_time_ms: start_processing_time.elapsed().as_millis() as u64,
         content_summary: page_title.clone(),
-        last_modified,
-        published_date,
+        last_modified: latest_of(DateKind::Modified),
+        published_date: latest_of(DateKind::Published),
+        earliest_date: all_dates.iter().filter_map(|date| date.date).min(),
+        latest_date: all_dates.iter().filter_map(|date| date.date).max(),
     };
     
-    Ok(DomainResult {
+    let domain_result = DomainResult {
         url: base_url.to_string(),
         title: page_title,
         content: full_content,
         matches: all_matches,
-        pages_crawled,
+        pages_crawled: pages.iter().filter(|page| page.status.is_some()).count(),
         has_more_pages,
         metadata: Some(metadata),
+        pages,
+        skipped_urls,
         error: None,
-    })
+    };
+    Ok((domain_result, page_terms))
 }
 
 fn process_page_content(
-    html_content: &str,
-    keywords: &[String],
-    all_matches: &mut Vec<KeywordMatch>,
+    page_text: &PageText,
+    request: &CrawlRequest,
+    match_fields: &[MatchField],
+    page_matches: &mut Vec<KeywordMatch>,
     time_limit: Option<Duration>,
     start_time: Instant,
     current_url: &Url,
-) -> Result<Option<String>, CrawlerError> {
-    let html_lowercase = html_content.to_lowercase();
-    let mut title = None;
-    
-    // Extract title if this is the first page
-    if all_matches.is_empty() {
-        let document = Html::parse_document(html_content);
-        let title_selector = Selector::parse("title").map_err(|e| CrawlerError::SelectorError(e.to_string()))?;
-        title = document.select(&title_selector).next().map(|element| element.inner_html());
-    }
+) -> Result<PageTerms, CrawlerError> {
+    // Keywords and page text go through the same normalization; the
+    // language may be detected from the page body
+    let normalizer = TextNormalizer::new(request.language.as_deref(), request.normalization.as_ref(), Some(&page_text.body));
+    let snippet_options = request.snippets.clone().unwrap_or_default();
+    let query = request.query.as_deref().map(|query| Query::parse(query, &normalizer)).transpose()?;
+    let matchers = compile_keywords(request, &normalizer)?;
     
-    for keyword in keywords {
-        // Check if we've exceeded the time limit
-        if let Some(limit) = time_limit {
-            if start_time.elapsed() > limit {
-                return Err(CrawlerError::TimeoutError);
-            }
+    for &field in match_fields {
+        let field_text = page_text.field(field);
+        if field_text.is_empty() {
+            continue;
         }
+        // Matching runs on a case-folded, normalized copy; offsets are mapped
+        // back so snippets are cut from the original text on char boundaries
+        let folded_text = normalizer.fold(field_text);
         
-        let keyword_lowercase = keyword.trim().to_lowercase();
-        let count = html_lowercase.matches(&keyword_lowercase).count();
-        
-        if count > 0 {
-            // Extract all contexts around the keyword
-            let mut contexts = Vec::new();
-            for (i, _) in html_lowercase.match_indices(&keyword_lowercase) {
-                // Check time limit again during processing
-                if let Some(limit) = time_limit {
-                    if start_time.elapsed() > limit {
-                        // If we hit the time limit, return what we have so far
-                        let context = "Time limit reached during processing".to_string();
-                        let cleaned_text = clean_html_text(&context);
-                        
-                        all_matches.push(KeywordMatch {
-                            keyword: keyword.clone(),
-                            context,
-                            cleaned_text,
-                            count,
-                            relevance_score: Some(0.0),
-                            source_url: current_url.to_string(),
-                        });
-                        
-                        return Err(CrawlerError::TimeoutError);
-                    }
+        for (keyword, matcher) in request.keywords.iter().zip(&matchers) {
+            // Check if we've exceeded the time limit
+            if let Some(limit) = time_limit {
+                if start_time.elapsed() > limit {
+                    return Err(CrawlerError::TimeoutError);
                 }
-                
-                let start = if i > 50 { i - 50 } else { 0 };
-                let end = if i + keyword.len() + 50 < html_content.len() {
-                    i + keyword.len() + 50
-                } else {
-                    html_content.len()
-                };
-                
-                let context = html_content[start..end].to_string();
-                contexts.push(context);
             }
             
-            // Include all contexts instead of just the first one
-            let context = if !contexts.is_empty() {
-                contexts.join("\n...\n")
-            } else {
-                "".to_string()
-            };
-            
-            // Clean the text and calculate relevance score
-            let cleaned_text = clean_html_text(&context);
-            let relevance_score = calculate_relevance_score(keyword, &context);
+            let occurrences = matcher.find(&folded_text);
+            let count = occurrences.len();
             
-            all_matches.push(KeywordMatch {
-                keyword: keyword.clone(),
-                context,
-                cleaned_text,
-                count,
-                relevance_score: Some(relevance_score),
-                source_url: current_url.to_string(),
-            });
+            if count > 0 {
+                // Extract snippets around the first occurrences
+                let snippets = snippet::collect_snippets(field_text, &occurrences, &snippet_options);
+                let context = snippets.join("\n...\n");
+                let cleaned_text = snippets.first().cloned().unwrap_or_default();
+                
+                page_matches.push(KeywordMatch {
+                    keyword: keyword.term().to_string(),
+                    field,
+                    context,
+                    cleaned_text,
+                    count,
+                    relevance_score: None, // Set once the whole crawl is scored
+                    source_url: current_url.to_string(),
+                    matched_clauses: Vec::new(),
+                });
+            }
+        }
+        
+        // The boolean query decides per field whether the page matches
+        if let Some(query) = &query {
+            let word_index = WordIndex::new(field_text, &normalizer);
+            if let Some(outcome) = query.evaluate(&word_index) {
+                let snippets = snippet::collect_snippets(field_text, &outcome.spans, &snippet_options);
+                
+                page_matches.push(KeywordMatch {
+                    keyword: request.query.clone().unwrap_or_default(),
+                    field,
+                    context: snippets.join("\n...\n"),
+                    cleaned_text: snippets.first().cloned().unwrap_or_default(),
+                    count: outcome.spans.len(),
+                    relevance_score: None,
+                    source_url: current_url.to_string(),
+                    matched_clauses: outcome.fired_clauses,
+                });
+            }
         }
     }
     
-    Ok(title)
+    // Title and body counts for BM25, independent of match_fields
+    let count_terms = |text: &str| {
+        let folded_text = normalizer.fold(text);
+        let mut counts: Vec<usize> = matchers.iter().map(|matcher| matcher.find(&folded_text).len()).collect();
+        if let Some(query) = &query {
+            counts.push(query.evaluate(&WordIndex::new(text, &normalizer)).map_or(0, |outcome| outcome.spans.len()));
+        }
+        counts
+    };
+    let title = page_text.title.as_deref().unwrap_or("");
+    
+    Ok(PageTerms {
+        url: current_url.to_string(),
+        title: page_text.title.clone(),
+        title_words: normalize::word_ranges(title).len(),
+        body_words: normalize::word_ranges(&page_text.body).len(),
+        title_counts: count_terms(title),
+        body_counts: count_terms(&page_text.body),
+    })
+}
+
+// Helper function to fingerprint cleaned page text
+fn content_hash(content: &str) -> String {
+    Sha256::digest(content.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+// Helper function to format the current time as Unix seconds
+fn unix_timestamp() -> String {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_else(|_| Duration::from_secs(0))
+        .as_secs();
+    format!("{}", timestamp)
+}
+
+// Helper function to compile every keyword for its match mode
+fn compile_keywords(request: &CrawlRequest, normalizer: &TextNormalizer) -> Result<Vec<KeywordMatcher>, CrawlerError> {
+    let default_mode = request.keyword_mode.unwrap_or_default();
+    request
+        .keywords
+        .iter()
+        .map(|keyword| KeywordMatcher::compile(keyword, default_mode, normalizer))
+        .collect()
 }
 
-fn find_next_page_url(document: &Html, current_url: &Url) -> Option<Url> {
-    // Common pagination selectors
-    let pagination_selectors = [
-        "a.next", "a.pagination-next", "a[rel='next']", 
-        "a:contains('Next')", "a:contains('next')", 
-        "a:contains('»')", "a.pagination__next", 
-        "li.next a", "div.pagination a:last-child",
-        ".pagination a[aria-label='Next']"
-    ];
-    
-    for selector_str in pagination_selectors {
-        if let Ok(selector) = Selector::parse(selector_str) {
-            if let Some(next_link) = document.select(&selector).next() {
-                if let Some(href) = next_link.value().attr("href") {
-                    // Convert relative URL to absolute
-                    if let Ok(next_url) = current_url.join(href) {
-                        return Some(next_url);
+// Helper function to collect absolute http(s) links from a page
+fn extract_links(document: &Html, current_url: &Url) -> Vec<Url> {
+    let mut links = Vec::new();
+    
+    if let Ok(link_selector) = Selector::parse("a[href]") {
+        for link in document.select(&link_selector) {
+            if let Some(href) = link.value().attr("href") {
+                if let Ok(mut link_url) = current_url.join(href) {
+                    if link_url.scheme() != "http" && link_url.scheme() != "https" {
+                        continue;
                     }
+                    // Fragments point into the same document
+                    link_url.set_fragment(None);
+                    links.push(link_url);
                 }
             }
         }
     }
     
-    None
-}
\ No newline at end of file
+    links
+}
+
+// Helper function to decide whether a discovered URL enters the frontier,
+// returning its canonical form. Pattern rejections are reported once in
+// skipped_urls; out-of-scope links are dropped silently since most pages link
+// to many other sites.
+fn admit_url(
+    url: &Url,
+    base_url: &Url,
+    url_filter: &UrlFilter,
+    canonicalizer: &UrlCanonicalizer,
+    visited_urls: &mut HashSet<String>,
+    skipped_urls: &mut Vec<SkippedUrl>,
+) -> Option<Url> {
+    let key = canonicalizer.key(url);
+    if visited_urls.contains(&key) {
+        return None;
+    }
+    
+    let canonical_url = canonicalizer.canonicalize(url);
+    if !url_filter.in_scope(&canonical_url, base_url) {
+        return None;
+    }
+    
+    visited_urls.insert(key);
+    if let Some(reason) = url_filter.pattern_rejection(&canonical_url) {
+        skipped_urls.push(SkippedUrl {
+            url: canonical_url.to_string(),
+            reason,
+        });
+        return None;
+    }
+    Some(canonical_url)
+}
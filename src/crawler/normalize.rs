@@ -0,0 +1,385 @@
+use rust_stemmers::{Algorithm, Stemmer};
+use serde::{Deserialize, Serialize};
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+// Stems shorter than this are left alone, since the Indonesian rules have no
+// dictionary to tell a root like "makan" from an affixed word
+const MIN_INDONESIAN_STEM_CHARS: usize = 4;
+
+// Optional normalization applied to both keywords and page text before
+// matching; every step is on unless switched off
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NormalizationOptions {
+    #[serde(default = "enabled")]
+    pub nfkc: bool, // Unicode compatibility folding (full-width forms, ligatures, ...)
+    #[serde(default = "enabled")]
+    pub strip_diacritics: bool, // "café" matches "cafe"
+    #[serde(default = "enabled")]
+    pub stemming: bool, // "running" matches "runs"; needs an English, Indonesian or Spanish `language` or detection
+}
+
+fn enabled() -> bool {
+    true
+}
+
+enum WordStemmer {
+    Snowball(Stemmer), // English and Spanish
+    Indonesian,
+}
+
+// Case folding plus the requested normalization steps, for one language
+pub struct TextNormalizer {
+    turkic: bool,
+    nfkc: bool,
+    strip_diacritics: bool,
+    stemmer: Option<WordStemmer>,
+}
+
+// Normalized copy of a text that remembers where each byte came from, so
+// matches found in the normalized text can be cut from the original
+pub struct FoldedText<'a> {
+    original: &'a str,
+    pub folded: String,
+    origins: Vec<(usize, usize)>, // Byte range in `original` that produced each folded byte
+}
+
+impl TextNormalizer {
+    // `language` is a BCP 47 tag such as "id" or "es-MX", or "auto" to detect
+    // it from `sample`. Without a tag the language is only detected when
+    // stemming is requested.
+    pub fn new(language: Option<&str>, options: Option<&NormalizationOptions>, sample: Option<&str>) -> Self {
+        let wants_stemming = options.is_some_and(|options| options.stemming);
+        let language_code = match language {
+            Some(tag) if !tag.eq_ignore_ascii_case("auto") => {
+                Some(tag.split(['-', '_']).next().unwrap_or("").to_ascii_lowercase())
+            }
+            Some(_) => sample.and_then(detect_language),
+            None if wants_stemming => sample.and_then(detect_language),
+            None => None,
+        };
+
+        let stemmer = match language_code.as_deref() {
+            _ if !wants_stemming => None,
+            Some("en") => Some(WordStemmer::Snowball(Stemmer::create(Algorithm::English))),
+            Some("es") => Some(WordStemmer::Snowball(Stemmer::create(Algorithm::Spanish))),
+            Some("id" | "in") => Some(WordStemmer::Indonesian),
+            _ => None,
+        };
+
+        Self {
+            turkic: matches!(language_code.as_deref(), Some("tr" | "az")),
+            nfkc: options.is_some_and(|options| options.nfkc),
+            strip_diacritics: options.is_some_and(|options| options.strip_diacritics),
+            stemmer,
+        }
+    }
+
+    pub fn fold<'a>(&self, original: &'a str) -> FoldedText<'a> {
+        let mut folded = String::with_capacity(original.len());
+        let mut origins = Vec::with_capacity(original.len());
+
+        // A base character and its combining marks are normalized together
+        let mut chars = original.char_indices().peekable();
+        while let Some((start, c)) = chars.next() {
+            let mut end = start + c.len_utf8();
+            while let Some(&(index, mark)) = chars.peek() {
+                if !is_combining_mark(mark) {
+                    break;
+                }
+                end = index + mark.len_utf8();
+                chars.next();
+            }
+
+            let before = folded.len();
+            self.fold_cluster(&original[start..end], &mut folded);
+            origins.resize(origins.len() + folded.len() - before, (start, end));
+        }
+
+        let folded_text = FoldedText { original, folded, origins };
+        match &self.stemmer {
+            Some(stemmer) => folded_text.stemmed(stemmer),
+            None => folded_text,
+        }
+    }
+
+    // Normalize a keyword or query term the same way as page text
+    pub fn normalize(&self, text: &str) -> String {
+        self.fold(text).folded
+    }
+
+    // Helper function to case-fold one character cluster, honoring the
+    // Turkish and Azerbaijani dotted and dotless I, then normalize it
+    fn fold_cluster(&self, cluster: &str, out: &mut String) {
+        let mut lowercase = String::with_capacity(cluster.len());
+        for c in cluster.chars() {
+            match c {
+                'I' if self.turkic => lowercase.push('ı'),
+                'İ' if self.turkic => lowercase.push('i'),
+                _ => lowercase.extend(c.to_lowercase()),
+            }
+        }
+
+        match (self.nfkc, self.strip_diacritics) {
+            (false, false) => out.push_str(&lowercase),
+            (true, false) => out.extend(lowercase.nfkc()),
+            (nfkc, true) => {
+                let without_marks = |c: &char| !is_combining_mark(*c);
+                if nfkc {
+                    out.extend(lowercase.nfkd().filter(without_marks));
+                } else {
+                    out.extend(lowercase.nfd().filter(without_marks));
+                }
+            }
+        }
+    }
+}
+
+impl<'a> FoldedText<'a> {
+    pub fn original(&self) -> &'a str {
+        self.original
+    }
+
+    // Byte range in the original text covering the folded range `start..end`
+    pub fn original_range(&self, start: usize, end: usize) -> (usize, usize) {
+        let original_start = self.origins.get(start).map_or(self.original.len(), |origin| origin.0);
+        let original_end = end
+            .checked_sub(1)
+            .and_then(|last| self.origins.get(last))
+            .map_or(original_start, |origin| origin.1);
+        (original_start, original_end.max(original_start))
+    }
+
+    // Helper function to replace every word with its stem; each stem maps
+    // back to its whole original word
+    fn stemmed(self, stemmer: &WordStemmer) -> Self {
+        let mut stemmed = String::with_capacity(self.folded.len());
+        let mut origins = Vec::with_capacity(self.origins.len());
+        let mut copied = 0;
+
+        for (start, end) in word_ranges(&self.folded) {
+            stemmed.push_str(&self.folded[copied..start]);
+            origins.extend_from_slice(&self.origins[copied..start]);
+
+            let word = &self.folded[start..end];
+            let stem = match stemmer {
+                WordStemmer::Snowball(stemmer) => stemmer.stem(word).into_owned(),
+                WordStemmer::Indonesian => stem_indonesian(word),
+            };
+            let stem = if stem.is_empty() { word.to_string() } else { stem };
+            let origin = (self.origins[start].0, self.origins[end - 1].1);
+            stemmed.push_str(&stem);
+            origins.resize(origins.len() + stem.len(), origin);
+            copied = end;
+        }
+        stemmed.push_str(&self.folded[copied..]);
+        origins.extend_from_slice(&self.origins[copied..]);
+
+        Self {
+            original: self.original,
+            folded: stemmed,
+            origins,
+        }
+    }
+}
+
+// Find the byte ranges of words (runs of letters and digits, including any
+// combining marks left in them)
+pub fn word_ranges(text: &str) -> Vec<(usize, usize)> {
+    let mut ranges = Vec::new();
+    let mut word_start = None;
+    for (index, c) in text.char_indices() {
+        let is_word_char = c.is_alphanumeric() || is_combining_mark(c);
+        match (is_word_char, word_start) {
+            (true, None) => word_start = Some(index),
+            (false, Some(start)) => {
+                ranges.push((start, index));
+                word_start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(start) = word_start {
+        ranges.push((start, text.len()));
+    }
+    ranges
+}
+
+// Helper function to detect the language of a text as an ISO 639-1 code
+fn detect_language(text: &str) -> Option<String> {
+    let code = match whatlang::detect(text)?.lang() {
+        whatlang::Lang::Eng => "en",
+        whatlang::Lang::Ind => "id",
+        whatlang::Lang::Spa => "es",
+        whatlang::Lang::Tur => "tr",
+        whatlang::Lang::Aze => "az",
+        _ => return None,
+    };
+    Some(code.to_string())
+}
+
+// Rule-based Indonesian stemmer after Tala (2003): particles, possessive
+// pronouns, then prefixes and derivational suffixes
+fn stem_indonesian(word: &str) -> String {
+    let mut stem = word.to_string();
+    for particle in ["kah", "lah", "pun"] {
+        if strip_suffix(&mut stem, particle) {
+            break;
+        }
+    }
+    for possessive in ["nya", "ku", "mu"] {
+        if strip_suffix(&mut stem, possessive) {
+            break;
+        }
+    }
+
+    if strip_first_order_prefix(&mut stem) {
+        strip_derivational_suffix(&mut stem);
+        strip_second_order_prefix(&mut stem);
+    } else {
+        strip_second_order_prefix(&mut stem);
+        strip_derivational_suffix(&mut stem);
+    }
+    stem
+}
+
+fn strip_derivational_suffix(stem: &mut String) {
+    for suffix in ["kan", "an", "i"] {
+        if strip_suffix(stem, suffix) {
+            break;
+        }
+    }
+}
+
+// Helper function to remove meN-, peN-, di-, ter- and ke-, restoring the
+// initial consonant the nasal replaced (menyapu → sapu, memukul → pukul,
+// menulis → tulis, mengirim → kirim). Without a dictionary, meng- and peng-
+// before a vowel always restore k, so vowel roots such as "ambil" are not
+// recovered from "mengambil".
+fn strip_first_order_prefix(stem: &mut String) -> bool {
+    let starts_with_vowel = |rest: &str| rest.starts_with(['a', 'e', 'i', 'o', 'u']);
+    for prefix in ["meng", "meny", "men", "mem", "me", "peng", "peny", "pen", "pem", "di", "ter", "ke"] {
+        let Some(rest) = stem.strip_prefix(prefix) else {
+            continue;
+        };
+        let replacement = match prefix {
+            "meny" | "peny" if starts_with_vowel(rest) => format!("s{}", rest),
+            "mem" | "pem" if starts_with_vowel(rest) => format!("p{}", rest),
+            "men" | "pen" if starts_with_vowel(rest) => format!("t{}", rest),
+            "meng" | "peng" if starts_with_vowel(rest) => format!("k{}", rest),
+            _ => rest.to_string(),
+        };
+        if replacement.chars().count() < MIN_INDONESIAN_STEM_CHARS {
+            return false;
+        }
+        *stem = replacement;
+        return true;
+    }
+    false
+}
+
+fn strip_second_order_prefix(stem: &mut String) -> bool {
+    for prefix in ["ber", "be", "per", "pe"] {
+        if let Some(rest) = stem.strip_prefix(prefix) {
+            if rest.chars().count() >= MIN_INDONESIAN_STEM_CHARS {
+                *stem = rest.to_string();
+                return true;
+            }
+            return false;
+        }
+    }
+    false
+}
+
+fn strip_suffix(stem: &mut String, suffix: &str) -> bool {
+    match stem.strip_suffix(suffix) {
+        Some(rest) if rest.chars().count() >= MIN_INDONESIAN_STEM_CHARS => {
+            stem.truncate(rest.len());
+            true
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(nfkc: bool, strip_diacritics: bool, stemming: bool) -> NormalizationOptions {
+        NormalizationOptions {
+            nfkc,
+            strip_diacritics,
+            stemming,
+        }
+    }
+
+    #[test]
+    fn indonesian_prefixes_restore_the_replaced_consonant() {
+        for (word, stem) in [
+            ("menulis", "tulis"),
+            ("penulis", "tulis"),
+            ("ditulis", "tulis"),
+            ("tulis", "tulis"),
+            ("mengirim", "kirim"),
+            ("pengirim", "kirim"),
+            ("kirim", "kirim"),
+            ("menyapu", "sapu"),
+            ("memukul", "pukul"),
+            ("membaca", "baca"),
+        ] {
+            assert_eq!(stem_indonesian(word), stem, "{}", word);
+        }
+    }
+
+    #[test]
+    fn indonesian_suffixes_and_short_stems() {
+        assert_eq!(stem_indonesian("dibacakan"), "baca");
+        assert_eq!(stem_indonesian("bukunya"), "buku");
+        assert_eq!(stem_indonesian("bermain"), "main");
+        assert_eq!(stem_indonesian("makanan"), "makan");
+        // Stripping would leave fewer than four characters
+        assert_eq!(stem_indonesian("makan"), "makan");
+        assert_eq!(stem_indonesian("dia"), "dia");
+    }
+
+    #[test]
+    fn indonesian_stemming_applies_to_page_text() {
+        let normalizer = TextNormalizer::new(Some("id"), Some(&options(true, true, true)), None);
+        assert_eq!(normalizer.normalize("Dia menulis surat"), "dia tulis surat");
+        assert_eq!(normalizer.normalize("tulis"), "tulis");
+    }
+
+    #[test]
+    fn fold_strips_diacritics_and_maps_back_to_the_original() {
+        let normalizer = TextNormalizer::new(None, Some(&options(false, true, false)), None);
+        let folded = normalizer.fold("Un Café crème");
+        assert_eq!(folded.folded, "un cafe creme");
+
+        let start = folded.folded.find("cafe").unwrap();
+        let (original_start, original_end) = folded.original_range(start, start + "cafe".len());
+        assert_eq!(&folded.original()[original_start..original_end], "Café");
+
+        // Decomposed input (e + combining acute) folds the same way
+        assert_eq!(normalizer.normalize("cafe\u{301}"), "cafe");
+    }
+
+    #[test]
+    fn fold_applies_nfkc_only_when_requested() {
+        let nfkc = TextNormalizer::new(None, Some(&options(true, false, false)), None);
+        assert_eq!(nfkc.normalize("ＢＡＮＫ ﬁnance"), "bank finance");
+        // Diacritics stay without strip_diacritics
+        assert_eq!(nfkc.normalize("Café"), "café");
+
+        let plain = TextNormalizer::new(None, None, None);
+        assert_eq!(plain.normalize("ＢＡＮＫ"), "ｂａｎｋ");
+    }
+
+    #[test]
+    fn turkic_languages_fold_dotted_and_dotless_i() {
+        let turkish = TextNormalizer::new(Some("tr"), None, None);
+        assert_eq!(turkish.normalize("İSTANBUL ILIK"), "istanbul ılık");
+
+        let english = TextNormalizer::new(Some("en"), None, None);
+        assert_eq!(english.normalize("ILIK"), "ilik");
+    }
+}
@@ -0,0 +1,184 @@
+use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use super::CrawlerError;
+
+// Valid CSS selectors for common "next page" links, tried in order
+const NEXT_PAGE_SELECTORS: [&str; 9] = [
+    "a[rel~='next']",
+    "link[rel~='next']",
+    "a.next",
+    "a.pagination-next",
+    "a.pagination__next",
+    "li.next a",
+    ".pagination a[aria-label='Next']",
+    "a[aria-label='Next page']",
+    "div.pagination a:last-child",
+];
+
+// Link texts that mean "next page" (English, Indonesian, Spanish), compared
+// case-insensitively after trimming. `scraper` has no `:contains()`, so text
+// matching has to be done by hand.
+const NEXT_PAGE_TEXTS: [&str; 11] = [
+    "next",
+    "next page",
+    "next »",
+    "»",
+    "›",
+    "older posts",
+    "selanjutnya",
+    "berikutnya",
+    "siguiente",
+    "página siguiente",
+    "siguiente »",
+];
+
+// How to find the next page of a paginated listing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PaginationStrategy {
+    // CSS selector for the next-page link; built-in heuristics are used when it matches nothing
+    Selector { selector: String },
+    // Template with a `{n}` placeholder resolved against the seed URL, e.g.
+    // "?page={n}" or "page/{n}/". The seed is page 1, so `n` starts at `start`.
+    Template {
+        template: String,
+        #[serde(default = "default_template_start")]
+        start: u64,
+        #[serde(default = "default_template_step")]
+        step: u64,
+        limit: Option<usize>, // Maximum number of generated pages
+    },
+    // Numeric offset query parameter on the seed URL, e.g. ?offset=20, ?offset=40, ...
+    Offset {
+        param: String,
+        page_size: u64,
+        #[serde(default)]
+        start: u64, // Offset of the seed page itself
+        limit: Option<usize>, // Maximum number of generated pages
+    },
+}
+
+fn default_template_start() -> u64 {
+    2
+}
+
+fn default_template_step() -> u64 {
+    1
+}
+
+impl PaginationStrategy {
+    pub fn validate(&self) -> Result<(), CrawlerError> {
+        match self {
+            PaginationStrategy::Selector { selector } => {
+                Selector::parse(selector).map_err(|e| CrawlerError::SelectorError(format!("{}: {}", selector, e)))?;
+            }
+            PaginationStrategy::Template { template, step, .. } => {
+                if !template.contains("{n}") {
+                    return Err(CrawlerError::PaginationError("template must contain {n}".to_string()));
+                }
+                if *step == 0 {
+                    return Err(CrawlerError::PaginationError("step must be greater than 0".to_string()));
+                }
+            }
+            PaginationStrategy::Offset { param, page_size, .. } => {
+                if param.is_empty() || *page_size == 0 {
+                    return Err(CrawlerError::PaginationError("offset pagination needs a param and a page_size greater than 0".to_string()));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // URL of page `index` (seed = 0) for the generated strategies
+    fn generated_url(&self, seed_url: &Url, index: usize) -> Option<Url> {
+        match self {
+            PaginationStrategy::Selector { .. } => None,
+            PaginationStrategy::Template { template, start, step, limit } => {
+                if index == 0 || limit.is_some_and(|limit| index > limit) {
+                    return None;
+                }
+                let n = start.checked_add(step.checked_mul(index as u64 - 1)?)?;
+                seed_url.join(&template.replace("{n}", &n.to_string())).ok()
+            }
+            PaginationStrategy::Offset { param, page_size, start, limit } => {
+                if index == 0 || limit.is_some_and(|limit| index > limit) {
+                    return None;
+                }
+                let offset = start.checked_add(page_size.checked_mul(index as u64)?)?;
+                let mut next_url = seed_url.clone();
+                let pairs: Vec<(String, String)> = seed_url
+                    .query_pairs()
+                    .filter(|(key, _)| key != param.as_str())
+                    .map(|(key, value)| (key.into_owned(), value.into_owned()))
+                    .collect();
+                next_url
+                    .query_pairs_mut()
+                    .clear()
+                    .extend_pairs(pairs)
+                    .append_pair(param, &offset.to_string());
+                Some(next_url)
+            }
+        }
+    }
+}
+
+// Find the next page for `page_url`. `page_index` is the page's position in
+// the seed's pagination chain (seed = 0) and is only known for pages reached
+// through pagination from the seed; generated strategies need it, and pages
+// outside the chain fall back to the built-in heuristics.
+pub fn next_page_url(
+    strategy: Option<&PaginationStrategy>,
+    document: &Html,
+    page_url: &Url,
+    seed_url: &Url,
+    page_index: Option<usize>,
+) -> Option<Url> {
+    match (strategy, page_index) {
+        (Some(PaginationStrategy::Selector { selector }), _) => {
+            find_with_selector(document, page_url, selector).or_else(|| find_next_page_url(document, page_url))
+        }
+        (Some(generated), Some(index)) => generated.generated_url(seed_url, index + 1),
+        _ => find_next_page_url(document, page_url),
+    }
+}
+
+// Helper function to resolve the href of the first element matching a selector
+fn find_with_selector(document: &Html, page_url: &Url, selector_str: &str) -> Option<Url> {
+    let selector = Selector::parse(selector_str).ok()?;
+    document
+        .select(&selector)
+        .filter_map(|element| element.value().attr("href"))
+        .find_map(|href| resolve_href(page_url, href))
+}
+
+// Helper function to turn an href into a crawlable URL, ignoring "#" and javascript: links
+fn resolve_href(page_url: &Url, href: &str) -> Option<Url> {
+    if href.trim().starts_with('#') {
+        return None;
+    }
+    page_url
+        .join(href)
+        .ok()
+        .filter(|url| url.scheme() == "http" || url.scheme() == "https")
+}
+
+// Built-in heuristics: well-known selectors first, then link text
+fn find_next_page_url(document: &Html, page_url: &Url) -> Option<Url> {
+    for selector_str in NEXT_PAGE_SELECTORS {
+        if let Some(next_url) = find_with_selector(document, page_url, selector_str) {
+            return Some(next_url);
+        }
+    }
+
+    let link_selector = Selector::parse("a[href]").ok()?;
+    document
+        .select(&link_selector)
+        .filter(|link| {
+            let text = link.text().collect::<String>().trim().to_lowercase();
+            NEXT_PAGE_TEXTS.contains(&text.as_str())
+        })
+        .filter_map(|link| link.value().attr("href"))
+        .find_map(|href| resolve_href(page_url, href))
+}
@@ -0,0 +1,169 @@
+use reqwest::StatusCode;
+use std::collections::HashMap;
+use std::env;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::time::Instant;
+use url::Url;
+
+// Hosts without requests for this long are forgotten, so the schedule does
+// not grow with every host the server has ever crawled
+const IDLE_HOST_TTL: Duration = Duration::from_secs(10 * 60);
+
+// Process-wide scheduler shared by every crawl running in this server
+static POLITENESS: OnceLock<Politeness> = OnceLock::new();
+
+pub fn global() -> &'static Politeness {
+    POLITENESS.get_or_init(|| Politeness::new(PolitenessConfig::from_env()))
+}
+
+#[derive(Debug, Clone)]
+pub struct PolitenessConfig {
+    pub min_delay: Duration,      // Minimum gap between request starts on one host
+    pub max_in_flight: usize,     // Maximum concurrent requests to one host
+    pub max_backoff: Duration,    // Upper bound for the adaptive delay after 429/503
+}
+
+impl PolitenessConfig {
+    pub fn from_env() -> Self {
+        let min_delay_ms = env::var("CRAWLER_MIN_DELAY_MS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(1000);
+
+        let max_in_flight = env::var("CRAWLER_MAX_IN_FLIGHT_PER_HOST")
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+            .unwrap_or(2)
+            .max(1);
+
+        let max_backoff_ms = env::var("CRAWLER_MAX_BACKOFF_MS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(60_000);
+
+        Self {
+            min_delay: Duration::from_millis(min_delay_ms),
+            max_in_flight,
+            max_backoff: Duration::from_millis(max_backoff_ms.max(min_delay_ms)),
+        }
+    }
+}
+
+struct HostSchedule {
+    next_slot: Instant,  // Earliest time the next request may start
+    delay: Duration,     // Current (possibly backed-off) delay between requests
+}
+
+struct HostState {
+    in_flight: Arc<Semaphore>,
+    schedule: Mutex<HostSchedule>,
+}
+
+pub struct Politeness {
+    config: PolitenessConfig,
+    hosts: Mutex<HashMap<String, Arc<HostState>>>,
+}
+
+// Held while a request to the host is in flight
+pub struct HostPermit {
+    host: Arc<HostState>,
+    config: PolitenessConfig,
+    _permit: OwnedSemaphorePermit,
+}
+
+impl Politeness {
+    pub fn new(config: PolitenessConfig) -> Self {
+        Self {
+            config,
+            hosts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn host_state(&self, url: &Url) -> Arc<HostState> {
+        let host = url.host_str().unwrap_or("").to_ascii_lowercase();
+        let mut hosts = self.hosts.lock().unwrap_or_else(|e| e.into_inner());
+        if !hosts.contains_key(&host) {
+            evict_idle_hosts(&mut hosts);
+        }
+        hosts
+            .entry(host)
+            .or_insert_with(|| {
+                Arc::new(HostState {
+                    in_flight: Arc::new(Semaphore::new(self.config.max_in_flight)),
+                    schedule: Mutex::new(HostSchedule {
+                        next_slot: Instant::now(),
+                        delay: self.config.min_delay,
+                    }),
+                })
+            })
+            .clone()
+    }
+
+    // Wait for an in-flight slot and the host's next start time. `crawl_delay`
+    // from robots.txt raises the minimum delay for this host. Returns None
+    // without reserving a slot if the request could not start before `deadline`.
+    pub async fn acquire(
+        &self,
+        url: &Url,
+        crawl_delay: Option<Duration>,
+        deadline: Option<Instant>,
+    ) -> Option<HostPermit> {
+        let host = self.host_state(url);
+        let permit = host.in_flight.clone().acquire_owned().await.ok()?;
+
+        let start_at = {
+            let mut schedule = host.schedule.lock().unwrap_or_else(|e| e.into_inner());
+            let start_at = schedule.next_slot.max(Instant::now());
+            if deadline.is_some_and(|deadline| start_at > deadline) {
+                return None;
+            }
+            let delay = schedule.delay.max(crawl_delay.unwrap_or_default());
+            schedule.next_slot = start_at + delay;
+            start_at
+        };
+        tokio::time::sleep_until(start_at).await;
+
+        Some(HostPermit {
+            host,
+            config: self.config.clone(),
+            _permit: permit,
+        })
+    }
+}
+
+// Helper function to drop hosts nobody is waiting on or holding a permit for,
+// whose last scheduled start is older than IDLE_HOST_TTL
+fn evict_idle_hosts(hosts: &mut HashMap<String, Arc<HostState>>) {
+    let now = Instant::now();
+    hosts.retain(|_, state| {
+        // The map's own reference is the only one when the host is unused
+        if Arc::strong_count(state) > 1 {
+            return true;
+        }
+        let schedule = state.schedule.lock().unwrap_or_else(|e| e.into_inner());
+        schedule.next_slot + IDLE_HOST_TTL > now
+    });
+}
+
+impl HostPermit {
+    // Adapt the host's delay to the response: back off on 429/503 and
+    // gradually return to the configured minimum on success
+    pub fn record_status(&self, status: StatusCode) {
+        let mut schedule = self.host.schedule.lock().unwrap_or_else(|e| e.into_inner());
+        if status == StatusCode::TOO_MANY_REQUESTS || status == StatusCode::SERVICE_UNAVAILABLE {
+            let doubled = (schedule.delay * 2).max(Duration::from_secs(1));
+            schedule.delay = doubled.min(self.config.max_backoff);
+            schedule.next_slot = schedule.next_slot.max(Instant::now() + schedule.delay);
+        } else if status.is_success() {
+            schedule.delay = (schedule.delay * 3 / 4).max(self.config.min_delay);
+        }
+    }
+
+    // Keep every crawl off this host for `wait` (e.g. a Retry-After value)
+    pub fn defer(&self, wait: Duration) {
+        let mut schedule = self.host.schedule.lock().unwrap_or_else(|e| e.into_inner());
+        schedule.next_slot = schedule.next_slot.max(Instant::now() + wait);
+    }
+}
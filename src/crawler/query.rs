@@ -0,0 +1,436 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use super::normalize::{self, TextNormalizer};
+use super::CrawlerError;
+
+// Deepest nesting of parentheses, NOT and NEAR a query may use; the parser
+// and the evaluation recurse once per level
+const MAX_QUERY_DEPTH: usize = 32;
+
+// Boolean keyword query such as `("data breach" OR leak) AND bank NOT "press release"`.
+// Operators are upper case: AND, OR, NOT and NEAR/n (both sides within n
+// words of each other). Adjacent terms are joined with AND, and NOT binds
+// tighter than AND, which binds tighter than OR. Terms and quoted phrases
+// match whole words, case-insensitively and after any normalization.
+#[derive(Debug, Clone)]
+pub enum Query {
+    Phrase { text: String, words: Vec<String> }, // A bare term is a one-word phrase
+    And(Vec<Query>),
+    Or(Vec<Query>),
+    Not(Box<Query>),
+    Near { left: Box<Query>, right: Box<Query>, distance: usize },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    LeftParen,
+    RightParen,
+    Phrase(String),
+    Term(String),
+    And,
+    Or,
+    Not,
+    Near(usize),
+}
+
+// Words of a text with their byte ranges in the original text
+pub struct WordIndex<'a> {
+    text: &'a str,
+    words: Vec<(String, usize, usize)>,
+    positions: HashMap<String, Vec<usize>>,
+}
+
+// Result of evaluating a query against one text
+pub struct QueryOutcome {
+    pub fired_clauses: Vec<String>, // Terms, phrases and NEAR clauses that matched outside a NOT
+    pub spans: Vec<(usize, usize)>, // Byte ranges of the matching words, for snippets
+}
+
+impl Query {
+    pub fn parse(input: &str, normalizer: &TextNormalizer) -> Result<Self, CrawlerError> {
+        let tokens = tokenize(input)?;
+        if tokens.is_empty() {
+            return Err(CrawlerError::QueryError("query is empty".to_string()));
+        }
+        let mut parser = Parser { tokens, position: 0, depth: 0, normalizer };
+        let query = parser.parse_or()?;
+        match parser.tokens.get(parser.position) {
+            None => Ok(query),
+            Some(token) => Err(CrawlerError::QueryError(format!("unexpected {:?}", token))),
+        }
+    }
+
+    // Evaluate the query; `None` means the text does not match
+    pub fn evaluate(&self, index: &WordIndex) -> Option<QueryOutcome> {
+        if !self.matches(index) {
+            return None;
+        }
+        let mut outcome = QueryOutcome {
+            fired_clauses: Vec::new(),
+            spans: Vec::new(),
+        };
+        self.collect_fired(index, &mut outcome);
+        outcome.spans.sort_unstable();
+        outcome.spans.dedup();
+        Some(outcome)
+    }
+
+    fn matches(&self, index: &WordIndex) -> bool {
+        match self {
+            Query::And(children) => children.iter().all(|child| child.matches(index)),
+            Query::Or(children) => children.iter().any(|child| child.matches(index)),
+            Query::Not(child) => !child.matches(index),
+            positional => !positional.word_spans(index).is_empty(),
+        }
+    }
+
+    // Word ranges (first word, one past the last word) where a positional clause matches
+    fn word_spans(&self, index: &WordIndex) -> Vec<(usize, usize)> {
+        match self {
+            Query::Phrase { words, .. } => index.phrase_spans(words),
+            Query::Or(children) => children.iter().flat_map(|child| child.word_spans(index)).collect(),
+            Query::Near { left, right, distance } => {
+                let left_spans = left.word_spans(index);
+                let right_spans = right.word_spans(index);
+                let mut spans = Vec::new();
+                for &(left_start, left_end) in &left_spans {
+                    for &(right_start, right_end) in &right_spans {
+                        // Words between the two spans; overlapping spans have no gap
+                        let gap = right_start.saturating_sub(left_end).max(left_start.saturating_sub(right_end));
+                        if gap <= *distance {
+                            spans.push((left_start.min(right_start), left_end.max(right_end)));
+                        }
+                    }
+                }
+                spans
+            }
+            Query::And(_) | Query::Not(_) => Vec::new(),
+        }
+    }
+
+    fn is_positional(&self) -> bool {
+        match self {
+            Query::Phrase { .. } | Query::Near { .. } => true,
+            Query::Or(children) => children.iter().all(Query::is_positional),
+            Query::And(_) | Query::Not(_) => false,
+        }
+    }
+
+    fn collect_fired(&self, index: &WordIndex, outcome: &mut QueryOutcome) {
+        match self {
+            Query::And(children) | Query::Or(children) => {
+                for child in children {
+                    child.collect_fired(index, outcome);
+                }
+            }
+            // Clauses under NOT fire by being absent and have nothing to show
+            Query::Not(_) => {}
+            positional => {
+                let spans = positional.word_spans(index);
+                if !spans.is_empty() {
+                    outcome.fired_clauses.push(positional.to_string());
+                    outcome.spans.extend(spans.into_iter().map(|(start, end)| index.byte_range(start, end)));
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let join = |f: &mut fmt::Formatter<'_>, children: &[Query], operator: &str| {
+            write!(f, "(")?;
+            for (i, child) in children.iter().enumerate() {
+                if i > 0 {
+                    write!(f, " {} ", operator)?;
+                }
+                write!(f, "{}", child)?;
+            }
+            write!(f, ")")
+        };
+        match self {
+            Query::Phrase { text, words } if words.len() > 1 => write!(f, "\"{}\"", text),
+            Query::Phrase { text, .. } => write!(f, "{}", text),
+            Query::And(children) => join(f, children, "AND"),
+            Query::Or(children) => join(f, children, "OR"),
+            Query::Not(child) => write!(f, "NOT {}", child),
+            Query::Near { left, right, distance } => write!(f, "{} NEAR/{} {}", left, distance, right),
+        }
+    }
+}
+
+impl<'a> WordIndex<'a> {
+    pub fn new(text: &'a str, normalizer: &TextNormalizer) -> Self {
+        let folded_text = normalizer.fold(text);
+        let mut words = Vec::new();
+        for (start, end) in normalize::word_ranges(&folded_text.folded) {
+            let (original_start, original_end) = folded_text.original_range(start, end);
+            words.push((folded_text.folded[start..end].to_string(), original_start, original_end));
+        }
+
+        let mut positions: HashMap<String, Vec<usize>> = HashMap::new();
+        for (position, (word, _, _)) in words.iter().enumerate() {
+            positions.entry(word.clone()).or_default().push(position);
+        }
+        Self { text, words, positions }
+    }
+
+    fn phrase_spans(&self, phrase: &[String]) -> Vec<(usize, usize)> {
+        let Some(first) = phrase.first().and_then(|word| self.positions.get(word)) else {
+            return Vec::new();
+        };
+        first
+            .iter()
+            .filter(|&&start| {
+                phrase
+                    .iter()
+                    .enumerate()
+                    .all(|(offset, word)| self.words.get(start + offset).is_some_and(|(w, _, _)| w == word))
+            })
+            .map(|&start| (start, start + phrase.len()))
+            .collect()
+    }
+
+    fn byte_range(&self, first_word: usize, end_word: usize) -> (usize, usize) {
+        let start = self.words.get(first_word).map_or(self.text.len(), |word| word.1);
+        let end = end_word
+            .checked_sub(1)
+            .and_then(|last| self.words.get(last))
+            .map_or(start, |word| word.2);
+        (start, end)
+    }
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    position: usize,
+    depth: usize, // Current nesting of parentheses, NOT and NEAR
+    normalizer: &'a TextNormalizer,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    // Helper function to go one nesting level deeper, within MAX_QUERY_DEPTH
+    fn descend(&mut self) -> Result<(), CrawlerError> {
+        self.depth += 1;
+        if self.depth > MAX_QUERY_DEPTH {
+            return Err(CrawlerError::QueryError(format!("query nests deeper than {} levels", MAX_QUERY_DEPTH)));
+        }
+        Ok(())
+    }
+
+    fn parse_or(&mut self) -> Result<Query, CrawlerError> {
+        let mut children = vec![self.parse_and()?];
+        while self.peek() == Some(&Token::Or) {
+            self.position += 1;
+            children.push(self.parse_and()?);
+        }
+        Ok(if children.len() == 1 { children.remove(0) } else { Query::Or(children) })
+    }
+
+    fn parse_and(&mut self) -> Result<Query, CrawlerError> {
+        let mut children = vec![self.parse_near()?];
+        loop {
+            match self.peek() {
+                Some(Token::And) => {
+                    self.position += 1;
+                    children.push(self.parse_near()?);
+                }
+                // Implicit AND, including `a NOT b`
+                Some(Token::LeftParen | Token::Phrase(_) | Token::Term(_) | Token::Not) => {
+                    children.push(self.parse_near()?);
+                }
+                _ => break,
+            }
+        }
+        Ok(if children.len() == 1 { children.remove(0) } else { Query::And(children) })
+    }
+
+    fn parse_near(&mut self) -> Result<Query, CrawlerError> {
+        let mut left = self.parse_unary()?;
+        // Every NEAR nests the clauses before it one level deeper
+        let depth = self.depth;
+        while let Some(&Token::Near(distance)) = self.peek() {
+            self.descend()?;
+            self.position += 1;
+            let right = self.parse_unary()?;
+            if !left.is_positional() || !right.is_positional() {
+                return Err(CrawlerError::QueryError(format!(
+                    "NEAR/{} needs terms or phrases on both sides",
+                    distance
+                )));
+            }
+            left = Query::Near {
+                left: Box::new(left),
+                right: Box::new(right),
+                distance,
+            };
+        }
+        self.depth = depth;
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> Result<Query, CrawlerError> {
+        match self.tokens.get(self.position).cloned() {
+            Some(Token::Not) => {
+                self.descend()?;
+                self.position += 1;
+                let inner = self.parse_unary()?;
+                self.depth -= 1;
+                Ok(Query::Not(Box::new(inner)))
+            }
+            Some(Token::LeftParen) => {
+                self.descend()?;
+                self.position += 1;
+                let inner = self.parse_or()?;
+                if self.peek() != Some(&Token::RightParen) {
+                    return Err(CrawlerError::QueryError("missing closing parenthesis".to_string()));
+                }
+                self.position += 1;
+                self.depth -= 1;
+                Ok(inner)
+            }
+            Some(Token::Phrase(text) | Token::Term(text)) => {
+                self.position += 1;
+                let folded = self.normalizer.normalize(&text);
+                let words: Vec<String> = normalize::word_ranges(&folded)
+                    .into_iter()
+                    .map(|(start, end)| folded[start..end].to_string())
+                    .collect();
+                if words.is_empty() {
+                    return Err(CrawlerError::QueryError(format!("\"{}\" contains no words", text)));
+                }
+                Ok(Query::Phrase { text, words })
+            }
+            Some(token) => Err(CrawlerError::QueryError(format!("unexpected {:?}", token))),
+            None => Err(CrawlerError::QueryError("query ends unexpectedly".to_string())),
+        }
+    }
+}
+
+// Helper function to split a query string into tokens
+fn tokenize(input: &str) -> Result<Vec<Token>, CrawlerError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            '(' => tokens.push(Token::LeftParen),
+            ')' => tokens.push(Token::RightParen),
+            '"' => {
+                let mut phrase = String::new();
+                let mut closed = false;
+                for (_, c) in chars.by_ref() {
+                    if c == '"' {
+                        closed = true;
+                        break;
+                    }
+                    phrase.push(c);
+                }
+                if !closed {
+                    return Err(CrawlerError::QueryError("unterminated quoted phrase".to_string()));
+                }
+                tokens.push(Token::Phrase(phrase.trim().to_string()));
+            }
+            _ => {
+                let mut end = start + c.len_utf8();
+                while let Some(&(index, next)) = chars.peek() {
+                    if next.is_whitespace() || matches!(next, '(' | ')' | '"') {
+                        break;
+                    }
+                    end = index + next.len_utf8();
+                    chars.next();
+                }
+                let word = &input[start..end];
+                tokens.push(match word {
+                    "AND" => Token::And,
+                    "OR" => Token::Or,
+                    "NOT" => Token::Not,
+                    _ => match word.strip_prefix("NEAR/") {
+                        Some(distance) => Token::Near(distance.parse().map_err(|_| {
+                            CrawlerError::QueryError(format!("invalid proximity in {}", word))
+                        })?),
+                        None => Token::Term(word.to_string()),
+                    },
+                });
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(query: &str) -> Result<Query, CrawlerError> {
+        Query::parse(query, &TextNormalizer::new(None, None, None))
+    }
+
+    fn matches(query: &str, text: &str) -> bool {
+        let normalizer = TextNormalizer::new(None, None, None);
+        let query = Query::parse(query, &normalizer).unwrap();
+        query.evaluate(&WordIndex::new(text, &normalizer)).is_some()
+    }
+
+    #[test]
+    fn precedence() {
+        // NOT binds tighter than AND, which binds tighter than OR
+        assert_eq!(parse("a OR b AND c").unwrap().to_string(), "(a OR (b AND c))");
+        assert_eq!(parse("a AND NOT b OR c").unwrap().to_string(), "((a AND NOT b) OR c)");
+        assert_eq!(parse("(a OR b) c").unwrap().to_string(), "((a OR b) AND c)");
+        assert_eq!(parse("a NOT b").unwrap().to_string(), "(a AND NOT b)");
+        assert_eq!(parse("a NEAR/3 b OR c").unwrap().to_string(), "(a NEAR/3 b OR c)");
+    }
+
+    #[test]
+    fn boolean_matching() {
+        let query = r#"("data breach" OR leak) AND bank NOT "press release""#;
+        assert!(matches(query, "A data breach at the bank"));
+        assert!(matches(query, "Bank leak reported"));
+        assert!(!matches(query, "Bank data was breached"));
+        assert!(!matches(query, "Press release: bank leak"));
+        assert!(!matches("a OR b AND c", "b only"));
+        assert!(matches("a OR b AND c", "a only"));
+    }
+
+    #[test]
+    fn near() {
+        assert!(matches("bank NEAR/2 fraud", "the bank reported fraud"));
+        assert!(matches("bank NEAR/2 fraud", "fraud at the bank"));
+        assert!(!matches("bank NEAR/1 fraud", "the bank reported large fraud"));
+        assert!(matches(r#""central bank" NEAR/0 fraud"#, "central bank fraud"));
+        assert!(parse("a NEAR/2 (b AND c)").is_err());
+        assert!(parse("a NEAR/x b").is_err());
+    }
+
+    #[test]
+    fn fired_clauses() {
+        let normalizer = TextNormalizer::new(None, None, None);
+        let query = Query::parse("(bank OR lender) NOT scam", &normalizer).unwrap();
+        let outcome = query.evaluate(&WordIndex::new("The bank said", &normalizer)).unwrap();
+        assert_eq!(outcome.fired_clauses, ["bank"]);
+        assert_eq!(outcome.spans, [(4, 8)]);
+    }
+
+    #[test]
+    fn invalid_queries() {
+        for query in ["", "a AND", "(a OR b", "a)", "\"unterminated", "\"  \""] {
+            assert!(matches!(parse(query), Err(CrawlerError::QueryError(_))), "{}", query);
+        }
+    }
+
+    #[test]
+    fn nesting_is_limited() {
+        assert!(parse(&format!("{}a{}", "(".repeat(MAX_QUERY_DEPTH), ")".repeat(MAX_QUERY_DEPTH))).is_ok());
+        assert!(parse(&format!("{}a{}", "(".repeat(20_000), ")".repeat(20_000))).is_err());
+        assert!(parse(&format!("{}a", "NOT ".repeat(20_000))).is_err());
+        assert!(parse(&format!("{}a", "a NEAR/1 ".repeat(20_000))).is_err());
+    }
+}
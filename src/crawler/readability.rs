@@ -0,0 +1,167 @@
+use ego_tree::NodeId;
+use scraper::{ElementRef, Html, Selector};
+use std::collections::HashMap;
+
+use super::text::collapse_whitespace;
+
+// Elements that hold page chrome rather than content. <header> is only
+// chrome outside the main content; inside it, it holds the headline.
+const BOILERPLATE_ELEMENTS: [&str; 6] = ["nav", "aside", "footer", "form", "menu", "dialog"];
+
+// Elements that are never content at all
+const NON_CONTENT_ELEMENTS: [&str; 8] = ["script", "style", "noscript", "template", "svg", "iframe", "button", "select"];
+
+// class/id words that mark boilerplate blocks and content blocks
+const BOILERPLATE_HINTS: [&str; 28] = [
+    "nav", "navbar", "navigation", "menu", "footer", "sidebar", "aside", "cookie", "cookies", "consent", "banner",
+    "comment", "comments", "share", "sharing", "social", "related", "recommended", "advert", "ads", "promo",
+    "breadcrumb", "breadcrumbs", "popup", "modal", "newsletter", "subscribe", "widget",
+];
+const CONTENT_HINTS: [&str; 10] = ["article", "body", "column", "content", "main", "post", "entry", "story", "text", "detail"];
+
+// Paragraphs shorter than this (in chars) don't vote for a container
+const MIN_PARAGRAPH_CHARS: usize = 25;
+
+// Find the main content of a page the way Readability does: every paragraph
+// scores its parent and grandparent by length and commas, the containers'
+// scores are scaled down by their link density, and the best container wins.
+// Navigation, asides, footers and blocks whose class or id looks like
+// boilerplate are removed from it. Returns `None` when no container stands
+// out, e.g. on pages without paragraphs of text.
+pub fn extract_main_content(document: &Html) -> Option<Html> {
+    let paragraph_selector = Selector::parse("p, pre, td, blockquote, div").ok()?;
+    let mut scores: HashMap<NodeId, f32> = HashMap::new();
+
+    for paragraph in document.select(&paragraph_selector) {
+        // A div only counts as a paragraph when it holds no other blocks
+        if paragraph.value().name() == "div" && has_block_children(paragraph) {
+            continue;
+        }
+        if in_boilerplate_element(paragraph) {
+            continue;
+        }
+        let text = collapsed_text(paragraph);
+        let length = text.chars().count();
+        if length < MIN_PARAGRAPH_CHARS {
+            continue;
+        }
+        let commas = text.matches([',', '，', '、', '،']).count();
+        let paragraph_score = 1.0 + commas as f32 + (length as f32 / 100.0).min(3.0);
+
+        let ancestors = paragraph.ancestors().filter_map(ElementRef::wrap).take(2);
+        for (level, ancestor) in ancestors.enumerate() {
+            if matches!(ancestor.value().name(), "html" | "body") {
+                break;
+            }
+            let score = scores.entry(ancestor.id()).or_insert_with(|| initial_score(ancestor));
+            *score += paragraph_score / (level + 1) as f32;
+        }
+    }
+
+    let (best_id, _) = scores
+        .iter()
+        .filter_map(|(&id, &score)| {
+            let element = ElementRef::wrap(document.tree.get(id)?)?;
+            Some((id, score * (1.0 - link_density(element))))
+        })
+        .filter(|&(_, score)| score > 0.0)
+        .max_by(|a, b| a.1.total_cmp(&b.1))?;
+
+    // Strip boilerplate from a copy; node ids are the same in the copy
+    let best = ElementRef::wrap(document.tree.get(best_id)?)?;
+    let boilerplate: Vec<NodeId> = best
+        .descendants()
+        .filter_map(ElementRef::wrap)
+        .filter(|element| is_boilerplate(*element))
+        .map(|element| element.id())
+        .collect();
+    let mut cleaned = document.clone();
+    for id in boilerplate {
+        if let Some(mut node) = cleaned.tree.get_mut(id) {
+            node.detach();
+        }
+    }
+    let main = ElementRef::wrap(cleaned.tree.get(best_id)?)?;
+    Some(Html::parse_fragment(&main.html()))
+}
+
+// Helper function to give a container its starting score from its tag and
+// its class and id
+fn initial_score(element: ElementRef) -> f32 {
+    let tag_score = match element.value().name() {
+        "article" | "main" => 10.0,
+        "div" => 5.0,
+        "pre" | "td" | "blockquote" => 3.0,
+        "address" | "ol" | "ul" | "dl" | "dd" | "dt" | "li" | "form" => -3.0,
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "th" => -5.0,
+        _ => 0.0,
+    };
+    let hints = hint_words(element);
+    let hint_score = if hints.iter().any(|word| CONTENT_HINTS.contains(&word.as_str())) {
+        25.0
+    } else if hints.iter().any(|word| BOILERPLATE_HINTS.contains(&word.as_str())) {
+        -25.0
+    } else {
+        0.0
+    };
+    tag_score + hint_score
+}
+
+// Helper function to decide whether an element inside the main content is
+// boilerplate: page chrome, non-content elements, hidden elements, or a
+// class/id that looks like chrome without also looking like content
+fn is_boilerplate(element: ElementRef) -> bool {
+    let name = element.value().name();
+    if BOILERPLATE_ELEMENTS.contains(&name) || NON_CONTENT_ELEMENTS.contains(&name) {
+        return true;
+    }
+    if element.value().attr("hidden").is_some() || element.value().attr("aria-hidden") == Some("true") {
+        return true;
+    }
+    let hints = hint_words(element);
+    hints.iter().any(|word| BOILERPLATE_HINTS.contains(&word.as_str()))
+        && !hints.iter().any(|word| CONTENT_HINTS.contains(&word.as_str()))
+}
+
+fn in_boilerplate_element(element: ElementRef) -> bool {
+    element
+        .ancestors()
+        .filter_map(ElementRef::wrap)
+        .any(|ancestor| ancestor.value().name() == "header" || BOILERPLATE_ELEMENTS.contains(&ancestor.value().name()))
+}
+
+fn has_block_children(element: ElementRef) -> bool {
+    element
+        .children()
+        .filter_map(ElementRef::wrap)
+        .any(|child| matches!(child.value().name(), "div" | "p" | "pre" | "table" | "ul" | "ol" | "blockquote" | "section" | "article"))
+}
+
+// Helper function to split class and id into lowercase words ("post-content__body" → post, content, body)
+fn hint_words(element: ElementRef) -> Vec<String> {
+    let value = element.value();
+    [value.attr("class"), value.attr("id")]
+        .into_iter()
+        .flatten()
+        .flat_map(|attribute| attribute.split(|c: char| !c.is_ascii_alphanumeric()))
+        .filter(|word| !word.is_empty())
+        .map(str::to_ascii_lowercase)
+        .collect()
+}
+
+// Share of an element's text that sits inside links
+fn link_density(element: ElementRef) -> f32 {
+    let total = collapsed_text(element).chars().count();
+    if total == 0 {
+        return 0.0;
+    }
+    let Ok(link_selector) = Selector::parse("a") else {
+        return 0.0;
+    };
+    let linked: usize = element.select(&link_selector).map(|link| collapsed_text(link).chars().count()).sum();
+    (linked as f32 / total as f32).min(1.0)
+}
+
+fn collapsed_text(element: ElementRef) -> String {
+    collapse_whitespace(&element.text().collect::<Vec<_>>().join(" "))
+}
@@ -0,0 +1,385 @@
+use reqwest::header::LOCATION;
+use reqwest::Client;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Mutex, OnceCell};
+use url::Url;
+
+use super::politeness;
+
+// Product token we look for in `User-agent:` lines (matched case-insensitively)
+pub const ROBOTS_USER_AGENT_TOKEN: &str = "rust-crawler";
+
+#[derive(Debug, Clone)]
+struct RobotsRule {
+    allow: bool,
+    pattern: String,
+}
+
+#[derive(Debug, Clone, Default)]
+struct RobotsGroup {
+    user_agents: Vec<String>,
+    rules: Vec<RobotsRule>,
+    crawl_delay: Option<f64>,
+}
+
+// Parsed robots.txt rules that apply to our user agent
+#[derive(Debug, Clone, Default)]
+pub struct RobotsRules {
+    rules: Vec<RobotsRule>,
+    crawl_delay: Option<Duration>,
+    disallow_all: bool,
+    sitemaps: Vec<String>, // `Sitemap:` URLs, which apply regardless of user agent
+}
+
+impl RobotsRules {
+    // Rules used when robots.txt does not exist (4xx): everything is allowed
+    pub fn allow_all() -> Self {
+        Self::default()
+    }
+
+    // Rules used when robots.txt is unreachable (5xx or network error): RFC 9309
+    // requires crawlers to assume a complete disallow in that case
+    pub fn disallow_all() -> Self {
+        Self {
+            disallow_all: true,
+            ..Self::default()
+        }
+    }
+
+    pub fn parse(content: &str, user_agent_token: &str) -> Self {
+        let mut groups: Vec<RobotsGroup> = Vec::new();
+        let mut current: Option<RobotsGroup> = None;
+        let mut last_was_user_agent = false;
+        let mut sitemaps = Vec::new();
+
+        for raw_line in content.lines() {
+            // Strip comments and surrounding whitespace
+            let line = raw_line.split('#').next().unwrap_or("").trim();
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let key = key.trim().to_ascii_lowercase();
+            let value = value.trim();
+
+            match key.as_str() {
+                "user-agent" => {
+                    // Consecutive user-agent lines share one group
+                    if !last_was_user_agent {
+                        if let Some(group) = current.take() {
+                            groups.push(group);
+                        }
+                        current = Some(RobotsGroup::default());
+                    }
+                    if let Some(group) = current.as_mut() {
+                        group.user_agents.push(value.to_ascii_lowercase());
+                    }
+                    last_was_user_agent = true;
+                }
+                "allow" | "disallow" => {
+                    last_was_user_agent = false;
+                    // An empty Disallow means "allow everything" and adds no rule
+                    if value.is_empty() {
+                        continue;
+                    }
+                    if let Some(group) = current.as_mut() {
+                        group.rules.push(RobotsRule {
+                            allow: key == "allow",
+                            pattern: value.to_string(),
+                        });
+                    }
+                }
+                "crawl-delay" => {
+                    last_was_user_agent = false;
+                    if let (Some(group), Ok(delay)) = (current.as_mut(), value.parse::<f64>()) {
+                        if delay.is_finite() && delay >= 0.0 {
+                            group.crawl_delay = Some(delay);
+                        }
+                    }
+                }
+                "sitemap" => {
+                    last_was_user_agent = false;
+                    if !value.is_empty() {
+                        sitemaps.push(value.to_string());
+                    }
+                }
+                _ => {
+                    last_was_user_agent = false;
+                }
+            }
+        }
+        if let Some(group) = current.take() {
+            groups.push(group);
+        }
+
+        // Prefer groups naming our product token, falling back to the `*` groups
+        let token = user_agent_token.to_ascii_lowercase();
+        let mut selected: Vec<&RobotsGroup> = groups
+            .iter()
+            .filter(|group| group.user_agents.iter().any(|agent| agent == &token))
+            .collect();
+        if selected.is_empty() {
+            selected = groups
+                .iter()
+                .filter(|group| group.user_agents.iter().any(|agent| agent == "*"))
+                .collect();
+        }
+
+        let rules = selected.iter().flat_map(|group| group.rules.iter().cloned()).collect();
+        let crawl_delay = selected
+            .iter()
+            .filter_map(|group| group.crawl_delay)
+            .reduce(f64::max)
+            .map(Duration::from_secs_f64);
+
+        Self {
+            rules,
+            crawl_delay,
+            disallow_all: false,
+            sitemaps,
+        }
+    }
+
+    pub fn crawl_delay(&self) -> Option<Duration> {
+        self.crawl_delay
+    }
+
+    pub fn sitemaps(&self) -> &[String] {
+        &self.sitemaps
+    }
+
+    // Whether these are the stand-in rules for an unreachable robots.txt
+    pub fn is_unavailable(&self) -> bool {
+        self.disallow_all
+    }
+
+    // Evaluate Allow/Disallow rules for a URL. The longest matching pattern wins
+    // and Allow wins ties, as specified by RFC 9309.
+    pub fn is_allowed(&self, url: &Url) -> bool {
+        if url.path() == "/robots.txt" {
+            return true;
+        }
+        if self.disallow_all {
+            return false;
+        }
+
+        let mut target = url.path().to_string();
+        if let Some(query) = url.query() {
+            target.push('?');
+            target.push_str(query);
+        }
+
+        let mut best: Option<(usize, bool)> = None;
+        for rule in &self.rules {
+            if !pattern_matches(&rule.pattern, &target) {
+                continue;
+            }
+            let length = rule.pattern.len();
+            best = match best {
+                Some((best_len, best_allow)) if best_len > length || (best_len == length && best_allow) => {
+                    Some((best_len, best_allow))
+                }
+                _ => Some((length, rule.allow)),
+            };
+        }
+
+        best.map(|(_, allow)| allow).unwrap_or(true)
+    }
+}
+
+// Helper function to match a robots.txt path pattern supporting `*` and a trailing `$`
+fn pattern_matches(pattern: &str, target: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(stripped) => (stripped, true),
+        None => (pattern, false),
+    };
+
+    let pattern = pattern.as_bytes();
+    let target = target.as_bytes();
+
+    // Iterative wildcard matching with backtracking on the last `*`
+    let (mut p, mut t) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    loop {
+        if p == pattern.len() {
+            if !anchored || t == target.len() {
+                return true;
+            }
+        } else if pattern[p] == b'*' {
+            star = Some((p, t));
+            p += 1;
+            continue;
+        } else if t < target.len() && pattern[p] == target[t] {
+            p += 1;
+            t += 1;
+            continue;
+        }
+
+        // Mismatch: let the last `*` swallow one more byte, if possible
+        match star {
+            Some((star_p, star_t)) if star_t < target.len() => {
+                star = Some((star_p, star_t + 1));
+                p = star_p + 1;
+                t = star_t + 1;
+            }
+            _ => return false,
+        }
+    }
+}
+
+// Per-host robots.txt cache shared by all domains of a crawl
+#[derive(Default)]
+pub struct RobotsCache {
+    entries: Mutex<HashMap<String, Arc<OnceCell<Arc<RobotsRules>>>>>,
+}
+
+impl RobotsCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Return the rules for the URL's origin, fetching robots.txt on first use
+    pub async fn rules_for(&self, client: &Client, url: &Url) -> Arc<RobotsRules> {
+        let origin = url.origin().ascii_serialization();
+        let cell = self.entries.lock().await.entry(origin).or_default().clone();
+
+        // The cell guarantees a single fetch per host without blocking other hosts
+        cell.get_or_init(|| async { Arc::new(fetch_robots(client, url).await) })
+            .await
+            .clone()
+    }
+}
+
+// Redirects followed for robots.txt itself (RFC 9309 asks for at least five)
+const MAX_ROBOTS_REDIRECTS: usize = 5;
+
+async fn fetch_robots(client: &Client, url: &Url) -> RobotsRules {
+    let mut robots_url = match url.join("/robots.txt") {
+        Ok(robots_url) => robots_url,
+        Err(_) => return RobotsRules::allow_all(),
+    };
+
+    // The crawler client does not follow redirects, so follow them here.
+    // Each request goes through the politeness scheduler like any page.
+    for _ in 0..=MAX_ROBOTS_REDIRECTS {
+        let Some(host_permit) = politeness::global().acquire(&robots_url, None, None).await else {
+            return RobotsRules::disallow_all();
+        };
+        let response = match client.get(robots_url.clone()).send().await {
+            Ok(response) => response,
+            Err(e) => {
+                eprintln!("Failed to fetch {}: {}", robots_url, e);
+                return RobotsRules::disallow_all();
+            }
+        };
+
+        let status = response.status();
+        host_permit.record_status(status);
+        if status.is_redirection() {
+            let location = response
+                .headers()
+                .get(LOCATION)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| robots_url.join(value).ok());
+            match location {
+                Some(next_url) => {
+                    robots_url = next_url;
+                    continue;
+                }
+                None => return RobotsRules::allow_all(),
+            }
+        }
+
+        return if status.is_success() {
+            let body = response.text().await;
+            drop(host_permit);
+            match body {
+                Ok(body) => RobotsRules::parse(&body, ROBOTS_USER_AGENT_TOKEN),
+                Err(e) => {
+                    eprintln!("Failed to read {}: {}", robots_url, e);
+                    RobotsRules::disallow_all()
+                }
+            }
+        } else if status.is_client_error() {
+            RobotsRules::allow_all()
+        } else {
+            eprintln!("robots.txt at {} returned {}, assuming full disallow", robots_url, status);
+            RobotsRules::disallow_all()
+        };
+    }
+
+    // Too many redirects: RFC 9309 treats robots.txt as unavailable, which
+    // (like a 4xx) allows everything; not to be confused with `disallow_all`
+    // for an unreachable robots.txt
+    RobotsRules::allow_all()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn allowed(robots: &RobotsRules, path: &str) -> bool {
+        robots.is_allowed(&Url::parse(&format!("https://example.com{}", path)).unwrap())
+    }
+
+    #[test]
+    fn longest_match_wins() {
+        let robots = RobotsRules::parse("User-agent: *\nDisallow: /private\nAllow: /private/public\n", ROBOTS_USER_AGENT_TOKEN);
+        assert!(!allowed(&robots, "/private/secret"));
+        assert!(allowed(&robots, "/private/public/page"));
+        assert!(allowed(&robots, "/other"));
+    }
+
+    #[test]
+    fn allow_wins_ties() {
+        let robots = RobotsRules::parse("User-agent: *\nDisallow: /page\nAllow: /page\n", ROBOTS_USER_AGENT_TOKEN);
+        assert!(allowed(&robots, "/page"));
+        let robots = RobotsRules::parse("User-agent: *\nAllow: /page\nDisallow: /page\n", ROBOTS_USER_AGENT_TOKEN);
+        assert!(allowed(&robots, "/page"));
+    }
+
+    #[test]
+    fn wildcards_and_end_anchor() {
+        let robots = RobotsRules::parse("User-agent: *\nDisallow: /*.pdf$\nDisallow: /search*q=\n", ROBOTS_USER_AGENT_TOKEN);
+        assert!(!allowed(&robots, "/files/report.pdf"));
+        assert!(allowed(&robots, "/files/report.pdf?download=1"));
+        assert!(allowed(&robots, "/files/report.pdfx"));
+        assert!(!allowed(&robots, "/search/results?q=rust"));
+        assert!(allowed(&robots, "/search/results"));
+    }
+
+    #[test]
+    fn pattern_matching() {
+        assert!(pattern_matches("/a*b*c", "/axxbyyc"));
+        assert!(pattern_matches("/a", "/abc"));
+        assert!(!pattern_matches("/a$", "/abc"));
+        assert!(pattern_matches("/a$", "/a"));
+        assert!(pattern_matches("*", "/anything"));
+        assert!(!pattern_matches("/a*c$", "/abcd"));
+    }
+
+    #[test]
+    fn own_group_replaces_wildcard_group() {
+        let content = "User-agent: *\nDisallow: /\n\nUser-agent: Other\nUser-agent: rust-crawler\nDisallow: /admin\nCrawl-delay: 2\n";
+        let robots = RobotsRules::parse(content, ROBOTS_USER_AGENT_TOKEN);
+        assert!(allowed(&robots, "/news"));
+        assert!(!allowed(&robots, "/admin/users"));
+        assert_eq!(robots.crawl_delay(), Some(Duration::from_secs(2)));
+    }
+
+    #[test]
+    fn empty_disallow_and_sitemaps() {
+        let robots = RobotsRules::parse("Sitemap: https://example.com/sitemap.xml\nUser-agent: *\nDisallow:\n", ROBOTS_USER_AGENT_TOKEN);
+        assert!(allowed(&robots, "/anything"));
+        assert_eq!(robots.sitemaps(), ["https://example.com/sitemap.xml"]);
+    }
+
+    #[test]
+    fn unavailable_disallows_everything_but_robots_txt() {
+        let robots = RobotsRules::disallow_all();
+        assert!(robots.is_unavailable());
+        assert!(!allowed(&robots, "/"));
+        assert!(allowed(&robots, "/robots.txt"));
+    }
+}
@@ -0,0 +1,282 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use super::{CrawlerError, SkipReason};
+
+// Second-level labels that sit under a country-code TLD (example.co.id,
+// example.go.id, example.com.es, ...). This approximates the public suffix
+// list for the registrable-domain scope without shipping the full list.
+const COMMON_SECOND_LEVEL_LABELS: [&str; 14] = [
+    "ac", "co", "com", "edu", "go", "gob", "gov", "mil", "net", "or", "org", "sch", "web", "my",
+];
+
+// Which hosts links may lead to, relative to the seed URL
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScopePolicy {
+    #[default]
+    SameHost,   // Only the seed's exact host
+    SameDomain, // Any host under the seed's registrable domain (news.example.com, shop.example.com)
+    Subdomains, // The seed's host and hosts below it
+    Allowlist,  // The seed's host and the hosts listed in `allowed_hosts`
+}
+
+// Scope policy plus include/exclude URL patterns, compiled once per request.
+// Patterns starting with `re:` are regular expressions searched in the full
+// URL. Anything else is a glob where `*` matches within one path segment and
+// `**` matches across segments; globs starting with `/` are matched against
+// the path and query, others against the full URL.
+#[derive(Debug)]
+pub struct UrlFilter {
+    scope: ScopePolicy,
+    allowed_hosts: Vec<String>,
+    include: Vec<UrlPattern>,
+    exclude: Vec<UrlPattern>,
+}
+
+#[derive(Debug)]
+struct UrlPattern {
+    regex: Regex,
+    path_only: bool,
+}
+
+impl UrlPattern {
+    fn compile(pattern: &str) -> Result<Self, CrawlerError> {
+        let invalid = |e: regex::Error| CrawlerError::InvalidPattern(format!("{}: {}", pattern, e));
+        if let Some(expression) = pattern.strip_prefix("re:") {
+            return Ok(Self {
+                regex: Regex::new(expression).map_err(invalid)?,
+                path_only: false,
+            });
+        }
+        Ok(Self {
+            regex: Regex::new(&glob_to_regex(pattern)).map_err(invalid)?,
+            path_only: pattern.starts_with('/'),
+        })
+    }
+
+    fn matches(&self, url: &Url) -> bool {
+        if self.path_only {
+            let mut target = url.path().to_string();
+            if let Some(query) = url.query() {
+                target.push('?');
+                target.push_str(query);
+            }
+            self.regex.is_match(&target)
+        } else {
+            self.regex.is_match(url.as_str())
+        }
+    }
+}
+
+impl UrlFilter {
+    pub fn new(
+        scope: ScopePolicy,
+        allowed_hosts: &[String],
+        include_patterns: &[String],
+        exclude_patterns: &[String],
+    ) -> Result<Self, CrawlerError> {
+        if scope == ScopePolicy::Allowlist && allowed_hosts.is_empty() {
+            return Err(CrawlerError::InvalidPattern("allowlist scope requires allowed_hosts".to_string()));
+        }
+
+        Ok(Self {
+            scope,
+            allowed_hosts: allowed_hosts.iter().map(|host| host.trim().to_lowercase()).collect(),
+            include: include_patterns.iter().map(|p| UrlPattern::compile(p)).collect::<Result<_, _>>()?,
+            exclude: exclude_patterns.iter().map(|p| UrlPattern::compile(p)).collect::<Result<_, _>>()?,
+        })
+    }
+
+    // Reason an in-scope URL is filtered out by the include/exclude patterns, if any
+    pub fn pattern_rejection(&self, url: &Url) -> Option<SkipReason> {
+        if !self.include.is_empty() && !self.include.iter().any(|pattern| pattern.matches(url)) {
+            return Some(SkipReason::NotIncluded);
+        }
+        if self.exclude.iter().any(|pattern| pattern.matches(url)) {
+            return Some(SkipReason::Excluded);
+        }
+        None
+    }
+
+    // Reason a redirect target may not be crawled from a crawl seeded at
+    // `seed_url`, if any
+    pub fn redirect_rejection(&self, url: &Url, seed_url: &Url) -> Option<SkipReason> {
+        if !self.in_scope(url, seed_url) {
+            return Some(SkipReason::OutOfScope);
+        }
+        self.pattern_rejection(url)
+    }
+
+    // Whether the scope policy lets a crawl seeded at `seed_url` reach `url`
+    pub fn in_scope(&self, url: &Url, seed_url: &Url) -> bool {
+        let (Some(host), Some(seed_host)) = (url.host_str(), seed_url.host_str()) else {
+            return false;
+        };
+        let host = host.to_lowercase();
+        let seed_host = seed_host.to_lowercase();
+        if host == seed_host {
+            return true;
+        }
+
+        match self.scope {
+            ScopePolicy::SameHost => false,
+            ScopePolicy::SameDomain => registrable_domain(&host) == registrable_domain(&seed_host),
+            ScopePolicy::Subdomains => is_subdomain_of(&host, &seed_host),
+            ScopePolicy::Allowlist => self.allowed_hosts.iter().any(|allowed| match allowed.strip_prefix("*.") {
+                Some(parent) => host == parent || is_subdomain_of(&host, parent),
+                None => host == *allowed,
+            }),
+        }
+    }
+}
+
+// Helper function to check that `host` lies strictly below `parent`
+fn is_subdomain_of(host: &str, parent: &str) -> bool {
+    host.strip_suffix(parent).is_some_and(|prefix| prefix.ends_with('.'))
+}
+
+// Helper function to approximate the registrable domain (eTLD+1) of a host
+fn registrable_domain(host: &str) -> String {
+    // IP addresses have no parent domain
+    if host.parse::<std::net::IpAddr>().is_ok() || host.starts_with('[') {
+        return host.to_string();
+    }
+
+    let labels: Vec<&str> = host.split('.').filter(|label| !label.is_empty()).collect();
+    let keep = match labels.as_slice() {
+        [.., second_level, tld] if tld.len() == 2 && COMMON_SECOND_LEVEL_LABELS.contains(second_level) => 3,
+        _ => 2,
+    };
+    labels[labels.len().saturating_sub(keep)..].join(".")
+}
+
+// Helper function to translate a URL glob into an anchored regular expression
+fn glob_to_regex(glob: &str) -> String {
+    let mut expression = String::from("^");
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '*' {
+            if chars.peek() == Some(&'*') {
+                chars.next();
+                expression.push_str(".*");
+            } else {
+                expression.push_str("[^/]*");
+            }
+        } else {
+            expression.push_str(&regex::escape(&c.to_string()));
+        }
+    }
+    expression.push('$');
+    expression
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(url: &str) -> Url {
+        Url::parse(url).unwrap()
+    }
+
+    fn filter(scope: ScopePolicy, allowed_hosts: &[&str]) -> UrlFilter {
+        let allowed_hosts: Vec<String> = allowed_hosts.iter().map(|host| host.to_string()).collect();
+        UrlFilter::new(scope, &allowed_hosts, &[], &[]).unwrap()
+    }
+
+    #[test]
+    fn globs_match_within_and_across_segments() {
+        let single = Regex::new(&glob_to_regex("/news/*")).unwrap();
+        assert!(single.is_match("/news/today"));
+        assert!(!single.is_match("/news/2024/today"));
+
+        let double = Regex::new(&glob_to_regex("/news/**")).unwrap();
+        assert!(double.is_match("/news/2024/today"));
+        assert!(!double.is_match("/blog/news/today"));
+
+        // Everything but `*` is literal
+        let literal = Regex::new(&glob_to_regex("/a.b?c=*")).unwrap();
+        assert!(literal.is_match("/a.b?c=1"));
+        assert!(!literal.is_match("/axb?c=1"));
+    }
+
+    #[test]
+    fn path_globs_see_the_query_and_full_globs_the_url() {
+        let include = vec!["/search?q=*".to_string()];
+        let exclude = vec!["https://example.com/login**".to_string(), "re:\\.pdf$".to_string()];
+        let url_filter = UrlFilter::new(ScopePolicy::SameHost, &[], &include, &exclude).unwrap();
+        assert_eq!(url_filter.pattern_rejection(&url("https://example.com/search?q=bank")), None);
+        assert_eq!(url_filter.pattern_rejection(&url("https://example.com/about")), Some(SkipReason::NotIncluded));
+
+        let url_filter = UrlFilter::new(ScopePolicy::SameHost, &[], &[], &exclude).unwrap();
+        assert_eq!(url_filter.pattern_rejection(&url("https://example.com/login/form")), Some(SkipReason::Excluded));
+        assert_eq!(url_filter.pattern_rejection(&url("https://example.com/report.pdf")), Some(SkipReason::Excluded));
+        assert_eq!(url_filter.pattern_rejection(&url("https://example.com/report.html")), None);
+    }
+
+    #[test]
+    fn invalid_patterns_are_rejected() {
+        let exclude = vec!["re:(".to_string()];
+        assert!(matches!(
+            UrlFilter::new(ScopePolicy::SameHost, &[], &[], &exclude),
+            Err(CrawlerError::InvalidPattern(_))
+        ));
+        assert!(matches!(
+            UrlFilter::new(ScopePolicy::Allowlist, &[], &[], &[]),
+            Err(CrawlerError::InvalidPattern(_))
+        ));
+    }
+
+    #[test]
+    fn registrable_domain_keeps_country_second_level_labels() {
+        assert_eq!(registrable_domain("news.example.com"), "example.com");
+        assert_eq!(registrable_domain("example.com"), "example.com");
+        assert_eq!(registrable_domain("www.example.co.id"), "example.co.id");
+        assert_eq!(registrable_domain("news.detik.go.id"), "detik.go.id");
+        assert_eq!(registrable_domain("shop.example.id"), "example.id");
+        // A two-letter TLD without a known second-level label is a plain domain
+        assert_eq!(registrable_domain("www.example.de"), "example.de");
+        assert_eq!(registrable_domain("10.0.0.1"), "10.0.0.1");
+    }
+
+    #[test]
+    fn scope_policies() {
+        let seed = url("https://www.example.co.id/");
+
+        let same_host = filter(ScopePolicy::SameHost, &[]);
+        assert!(same_host.in_scope(&url("https://WWW.example.co.id/a"), &seed));
+        assert!(!same_host.in_scope(&url("https://news.example.co.id/a"), &seed));
+
+        let same_domain = filter(ScopePolicy::SameDomain, &[]);
+        assert!(same_domain.in_scope(&url("https://news.example.co.id/a"), &seed));
+        assert!(!same_domain.in_scope(&url("https://other.co.id/a"), &seed));
+
+        let subdomains = filter(ScopePolicy::Subdomains, &[]);
+        assert!(subdomains.in_scope(&url("https://m.www.example.co.id/a"), &seed));
+        assert!(!subdomains.in_scope(&url("https://news.example.co.id/a"), &seed));
+        assert!(!subdomains.in_scope(&url("https://notwww.example.co.id/a"), &seed));
+    }
+
+    #[test]
+    fn allowlist_wildcards_cover_the_parent_and_its_subdomains() {
+        let seed = url("https://example.com/");
+        let allowlist = filter(ScopePolicy::Allowlist, &["cdn.example.net", " *.Example.org "]);
+        assert!(allowlist.in_scope(&url("https://example.com/a"), &seed));
+        assert!(allowlist.in_scope(&url("https://cdn.example.net/a"), &seed));
+        assert!(!allowlist.in_scope(&url("https://img.cdn.example.net/a"), &seed));
+        assert!(allowlist.in_scope(&url("https://example.org/a"), &seed));
+        assert!(allowlist.in_scope(&url("https://a.b.example.org/a"), &seed));
+        assert!(!allowlist.in_scope(&url("https://badexample.org/a"), &seed));
+    }
+
+    #[test]
+    fn redirects_are_rejected_outside_the_scope() {
+        let exclude = vec!["/login".to_string()];
+        let url_filter = UrlFilter::new(ScopePolicy::SameHost, &[], &[], &exclude).unwrap();
+        let seed = url("https://example.com/");
+        assert_eq!(url_filter.redirect_rejection(&url("https://tracker.test/"), &seed), Some(SkipReason::OutOfScope));
+        assert_eq!(url_filter.redirect_rejection(&url("https://example.com/login"), &seed), Some(SkipReason::Excluded));
+        assert_eq!(url_filter.redirect_rejection(&url("https://example.com/news"), &seed), None);
+    }
+}
@@ -0,0 +1,278 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::CrawlerError;
+
+// BM25F parameters; the title counts more than the body by default
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScoringOptions {
+    #[serde(default = "default_title_weight")]
+    pub title_weight: f32,
+    #[serde(default = "default_body_weight")]
+    pub body_weight: f32,
+    #[serde(default = "default_k1")]
+    pub k1: f32, // Term frequency saturation
+    #[serde(default = "default_b")]
+    pub b: f32, // Document length normalization (0 = none, 1 = full)
+}
+
+impl Default for ScoringOptions {
+    fn default() -> Self {
+        Self {
+            title_weight: default_title_weight(),
+            body_weight: default_body_weight(),
+            k1: default_k1(),
+            b: default_b(),
+        }
+    }
+}
+
+impl ScoringOptions {
+    pub fn validate(&self) -> Result<(), CrawlerError> {
+        let in_range = |value: f32, max: f32| value.is_finite() && (0.0..=max).contains(&value);
+        if !in_range(self.title_weight, f32::MAX) || !in_range(self.body_weight, f32::MAX) {
+            return Err(CrawlerError::ScoringError("weights must not be negative".to_string()));
+        }
+        if !in_range(self.k1, f32::MAX) {
+            return Err(CrawlerError::ScoringError("k1 must not be negative".to_string()));
+        }
+        if !in_range(self.b, 1.0) {
+            return Err(CrawlerError::ScoringError("b must be between 0 and 1".to_string()));
+        }
+        Ok(())
+    }
+}
+
+fn default_title_weight() -> f32 {
+    2.0
+}
+
+fn default_body_weight() -> f32 {
+    1.0
+}
+
+fn default_k1() -> f32 {
+    1.2
+}
+
+fn default_b() -> f32 {
+    0.75
+}
+
+// Term counts of one keyword-matched page; counts are indexed like the
+// scored terms (keywords in request order, then the query)
+#[derive(Debug)]
+pub struct PageTerms {
+    pub url: String,
+    pub title: Option<String>,
+    pub title_words: usize,
+    pub body_words: usize,
+    pub title_counts: Vec<usize>,
+    pub body_counts: Vec<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RankedPage {
+    pub url: String,
+    pub title: Option<String>,
+    pub score: f32, // 0-100, relative to the best page of the crawl
+    pub bm25: f32,  // Sum of the raw per-keyword BM25 scores
+    pub keyword_scores: Vec<KeywordScore>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct KeywordScore {
+    pub keyword: String,
+    pub score: f32, // 0-100, relative to the best page of the crawl for this keyword
+    pub bm25: f32,
+}
+
+// Score every page against every term with BM25F over the whole crawl and
+// return the pages with a positive score, best first. Scores are scaled to
+// 0-100 against the best page, so they compare pages within one crawl.
+pub fn rank_pages(terms: &[String], pages: &[PageTerms], options: &ScoringOptions) -> Vec<RankedPage> {
+    if pages.is_empty() || terms.is_empty() {
+        return Vec::new();
+    }
+
+    let page_count = pages.len() as f32;
+    let average_title = (pages.iter().map(|page| page.title_words).sum::<usize>() as f32 / page_count).max(1.0);
+    let average_body = (pages.iter().map(|page| page.body_words).sum::<usize>() as f32 / page_count).max(1.0);
+
+    let document_frequency: Vec<f32> = (0..terms.len())
+        .map(|term| {
+            pages
+                .iter()
+                .filter(|page| page.title_counts[term] + page.body_counts[term] > 0)
+                .count() as f32
+        })
+        .collect();
+
+    // Raw BM25F score of every page (rows) for every term (columns)
+    let raw: Vec<Vec<f32>> = pages
+        .iter()
+        .map(|page| {
+            // With b = 1 an empty field has a norm of 0, so fields without the
+            // term are skipped rather than divided
+            let title_norm = (1.0 - options.b + options.b * page.title_words as f32 / average_title).max(f32::EPSILON);
+            let body_norm = (1.0 - options.b + options.b * page.body_words as f32 / average_body).max(f32::EPSILON);
+            let field_frequency = |count: usize, weight: f32, norm: f32| {
+                if count == 0 {
+                    0.0
+                } else {
+                    weight * count as f32 / norm
+                }
+            };
+            (0..terms.len())
+                .map(|term| {
+                    let weighted_frequency = field_frequency(page.title_counts[term], options.title_weight, title_norm)
+                        + field_frequency(page.body_counts[term], options.body_weight, body_norm);
+                    if weighted_frequency <= 0.0 {
+                        return 0.0;
+                    }
+                    let df = document_frequency[term];
+                    let idf = (1.0 + (page_count - df + 0.5) / (df + 0.5)).ln();
+                    idf * weighted_frequency * (options.k1 + 1.0) / (options.k1 + weighted_frequency)
+                })
+                .collect()
+        })
+        .collect();
+
+    let best_per_term: Vec<f32> = (0..terms.len())
+        .map(|term| raw.iter().map(|scores| scores[term]).fold(0.0, f32::max))
+        .collect();
+    let totals: Vec<f32> = raw.iter().map(|scores| scores.iter().sum()).collect();
+    let best_total = totals.iter().copied().fold(0.0, f32::max);
+
+    let mut ranked: Vec<RankedPage> = pages
+        .iter()
+        .zip(&raw)
+        .zip(&totals)
+        .filter(|(_, &total)| total > 0.0)
+        .map(|((page, scores), &total)| RankedPage {
+            url: page.url.clone(),
+            title: page.title.clone(),
+            score: scale(total, best_total),
+            bm25: total,
+            keyword_scores: terms
+                .iter()
+                .zip(scores)
+                .zip(&best_per_term)
+                .filter(|((_, &score), _)| score > 0.0)
+                .map(|((term, &score), &best)| KeywordScore {
+                    keyword: term.clone(),
+                    score: scale(score, best),
+                    bm25: score,
+                })
+                .collect(),
+        })
+        .collect();
+
+    ranked.sort_by(|a, b| b.bm25.total_cmp(&a.bm25));
+    ranked
+}
+
+// Per-page, per-keyword scores on the 0-100 scale, for KeywordMatch
+pub fn keyword_scores(ranked: &[RankedPage]) -> HashMap<(&str, &str), f32> {
+    ranked
+        .iter()
+        .flat_map(|page| {
+            page.keyword_scores
+                .iter()
+                .map(move |keyword| ((page.url.as_str(), keyword.keyword.as_str()), keyword.score))
+        })
+        .collect()
+}
+
+// Helper function to scale a raw score to 0-100 against the best one, rounded to two decimals
+fn scale(score: f32, best: f32) -> f32 {
+    if best <= 0.0 {
+        return 0.0;
+    }
+    (score / best * 10_000.0).round() / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(url: &str, title_words: usize, body_words: usize, title_counts: Vec<usize>, body_counts: Vec<usize>) -> PageTerms {
+        PageTerms {
+            url: url.to_string(),
+            title: None,
+            title_words,
+            body_words,
+            title_counts,
+            body_counts,
+        }
+    }
+
+    fn terms(terms: &[&str]) -> Vec<String> {
+        terms.iter().map(|term| term.to_string()).collect()
+    }
+
+    #[test]
+    fn ranks_pages_by_term_frequency() {
+        let pages = vec![
+            page("https://a.test/once", 5, 100, vec![0], vec![1]),
+            page("https://a.test/often", 5, 100, vec![0], vec![5]),
+            page("https://a.test/never", 5, 100, vec![0], vec![0]),
+        ];
+        let ranked = rank_pages(&terms(&["bank"]), &pages, &ScoringOptions::default());
+        let urls: Vec<&str> = ranked.iter().map(|page| page.url.as_str()).collect();
+        assert_eq!(urls, vec!["https://a.test/often", "https://a.test/once"]);
+        assert_eq!(ranked[0].score, 100.0);
+        assert!(ranked[1].score > 0.0 && ranked[1].score < 100.0);
+    }
+
+    #[test]
+    fn title_matches_count_more_by_default() {
+        let pages = vec![
+            page("https://a.test/body", 5, 100, vec![0], vec![1]),
+            page("https://a.test/title", 5, 100, vec![1], vec![0]),
+        ];
+        let ranked = rank_pages(&terms(&["bank"]), &pages, &ScoringOptions::default());
+        assert_eq!(ranked[0].url, "https://a.test/title");
+    }
+
+    #[test]
+    fn rarer_terms_weigh_more() {
+        let pages = vec![
+            page("https://a.test/common", 5, 100, vec![0, 0], vec![1, 0]),
+            page("https://a.test/rare", 5, 100, vec![0, 0], vec![1, 1]),
+            page("https://a.test/other", 5, 100, vec![0, 0], vec![1, 0]),
+        ];
+        let ranked = rank_pages(&terms(&["bank", "leak"]), &pages, &ScoringOptions::default());
+        let rare = &ranked[0];
+        assert_eq!(rare.url, "https://a.test/rare");
+        let bank = rare.keyword_scores.iter().find(|score| score.keyword == "bank").unwrap();
+        let leak = rare.keyword_scores.iter().find(|score| score.keyword == "leak").unwrap();
+        assert!(leak.bm25 > bank.bm25);
+    }
+
+    #[test]
+    fn full_length_normalization_keeps_pages_without_a_title() {
+        let pages = vec![
+            page("https://a.test/untitled", 0, 50, vec![0], vec![2]),
+            page("https://a.test/titled", 4, 50, vec![0], vec![1]),
+        ];
+        let options = ScoringOptions { b: 1.0, ..Default::default() };
+        let ranked = rank_pages(&terms(&["bank"]), &pages, &options);
+        assert_eq!(ranked.len(), 2);
+        assert!(ranked.iter().all(|page| page.bm25.is_finite()));
+        assert_eq!(ranked[0].url, "https://a.test/untitled");
+
+        let scores = keyword_scores(&ranked);
+        assert_eq!(scores.get(&("https://a.test/untitled", "bank")), Some(&100.0));
+    }
+
+    #[test]
+    fn rejects_out_of_range_options() {
+        assert!(ScoringOptions::default().validate().is_ok());
+        assert!(ScoringOptions { b: 1.5, ..Default::default() }.validate().is_err());
+        assert!(ScoringOptions { b: -0.1, ..Default::default() }.validate().is_err());
+        assert!(ScoringOptions { k1: -1.0, ..Default::default() }.validate().is_err());
+        assert!(ScoringOptions { title_weight: -2.0, ..Default::default() }.validate().is_err());
+        assert!(ScoringOptions { body_weight: f32::NAN, ..Default::default() }.validate().is_err());
+    }
+}
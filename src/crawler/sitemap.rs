@@ -0,0 +1,168 @@
+use chrono::NaiveDate;
+use flate2::read::GzDecoder;
+use reqwest::Client;
+use std::cmp::Reverse;
+use std::collections::{HashSet, VecDeque};
+use std::io::Read;
+use tokio::time::Instant;
+use url::Url;
+
+use super::fetch::{self, RetryPolicy};
+use super::robots::RobotsCache;
+use super::date_in_range;
+use super::dates::parse_date_value;
+
+// Upper bounds so a huge sitemap tree cannot stall a crawl
+const MAX_SITEMAPS: usize = 50;
+const MAX_SITEMAP_URLS: usize = 50_000;
+
+// Largest sitemap the protocol allows (uncompressed), for both the download
+// and the gunzipped document
+const MAX_SITEMAP_BYTES: usize = 50 * 1024 * 1024;
+
+struct SitemapEntry {
+    url: Url,
+    lastmod: Option<NaiveDate>,
+}
+
+enum SitemapDocument {
+    Index(Vec<SitemapEntry>),  // <sitemapindex> pointing at further sitemaps
+    UrlSet(Vec<SitemapEntry>), // <urlset> listing page URLs
+}
+
+// Discover the sitemaps of a site (robots.txt `Sitemap:` lines, falling back to
+// /sitemap.xml) and return the page URLs they list, newest `<lastmod>` first.
+// Entries whose `<lastmod>` falls outside the date range are dropped; entries
+// without one are kept.
+pub async fn discover_urls(
+    client: &Client,
+    base_url: &Url,
+    policy: &RetryPolicy,
+    robots_cache: &RobotsCache,
+    deadline: Option<Instant>,
+    date_from: Option<NaiveDate>,
+    date_to: Option<NaiveDate>,
+) -> Vec<Url> {
+    let robots = robots_cache.rules_for(client, base_url).await;
+    let mut queue: VecDeque<Url> = robots
+        .sitemaps()
+        .iter()
+        .filter_map(|sitemap| Url::parse(sitemap).ok())
+        .collect();
+    if queue.is_empty() {
+        if let Ok(default_sitemap) = base_url.join("/sitemap.xml") {
+            queue.push_back(default_sitemap);
+        }
+    }
+
+    let mut seen = HashSet::new();
+    let mut entries = Vec::new();
+    let mut sitemaps_read = 0;
+
+    while let Some(sitemap_url) = queue.pop_front() {
+        if sitemaps_read >= MAX_SITEMAPS || entries.len() >= MAX_SITEMAP_URLS {
+            break;
+        }
+        if !seen.insert(sitemap_url.to_string()) {
+            continue;
+        }
+        sitemaps_read += 1;
+
+        let Some(body) = fetch_sitemap(client, &sitemap_url, policy, robots_cache, deadline).await else {
+            continue;
+        };
+
+        match parse_sitemap(&body) {
+            Ok(SitemapDocument::Index(children)) => {
+                // A sitemap last changed before date_from cannot list newer pages
+                for child in children {
+                    if child.lastmod.is_none_or(|lastmod| date_in_range(lastmod, date_from, None)) {
+                        queue.push_back(child.url);
+                    }
+                }
+            }
+            Ok(SitemapDocument::UrlSet(urls)) => {
+                entries.extend(urls.into_iter().filter(|entry| {
+                    entry.lastmod.is_none_or(|lastmod| date_in_range(lastmod, date_from, date_to))
+                }));
+            }
+            Err(e) => {
+                eprintln!("Failed to parse sitemap {}: {}", sitemap_url, e);
+            }
+        }
+    }
+
+    // Newest first (undated entries last) so date-bounded crawls reach fresh pages early
+    entries.sort_by_key(|entry| Reverse(entry.lastmod));
+    entries.truncate(MAX_SITEMAP_URLS);
+    entries.into_iter().map(|entry| entry.url).collect()
+}
+
+// Helper function to download a sitemap, transparently gunzipping `.xml.gz` files
+async fn fetch_sitemap(
+    client: &Client,
+    sitemap_url: &Url,
+    policy: &RetryPolicy,
+    robots_cache: &RobotsCache,
+    deadline: Option<Instant>,
+) -> Option<String> {
+    let outcome = fetch::fetch_page(client, sitemap_url, policy, robots_cache, None, deadline).await;
+    let response = outcome.result.ok()?;
+    if !response.status().is_success() {
+        return None;
+    }
+    let body = fetch::until_deadline(deadline, fetch::read_capped(response, MAX_SITEMAP_BYTES)).await?.ok()?;
+    drop(outcome.host_permit);
+    let Some(bytes) = body else {
+        eprintln!("Sitemap {} is larger than {} bytes, skipping it", sitemap_url, MAX_SITEMAP_BYTES);
+        return None;
+    };
+
+    // Detect gzip by its magic bytes rather than trusting the file name
+    if bytes.starts_with(&[0x1f, 0x8b]) {
+        // Read one byte past the limit to tell a full-size sitemap from a larger one
+        let mut decoded = String::new();
+        match GzDecoder::new(&bytes[..]).take(MAX_SITEMAP_BYTES as u64 + 1).read_to_string(&mut decoded) {
+            Ok(size) if size > MAX_SITEMAP_BYTES => {
+                eprintln!("Sitemap {} decompresses to more than {} bytes, skipping it", sitemap_url, MAX_SITEMAP_BYTES);
+                None
+            }
+            Ok(_) => Some(decoded),
+            Err(e) => {
+                eprintln!("Failed to decompress sitemap {}: {}", sitemap_url, e);
+                None
+            }
+        }
+    } else {
+        Some(String::from_utf8_lossy(&bytes).into_owned())
+    }
+}
+
+fn parse_sitemap(xml: &str) -> Result<SitemapDocument, roxmltree::Error> {
+    let document = roxmltree::Document::parse(xml)?;
+    let root = document.root_element();
+    let is_index = root.has_tag_name("sitemapindex");
+    let item_tag = if is_index { "sitemap" } else { "url" };
+
+    let entries = root
+        .children()
+        .filter(|node| node.has_tag_name(item_tag))
+        .filter_map(|item| {
+            let child_text = |name: &str| {
+                item.children()
+                    .find(|node| node.has_tag_name(name))
+                    .and_then(|node| node.text())
+                    .map(str::trim)
+            };
+            let url = Url::parse(child_text("loc")?).ok()?;
+            let lastmod = child_text("lastmod").and_then(parse_date_value);
+            Some(SitemapEntry { url, lastmod })
+        })
+        .collect();
+
+    Ok(if is_index {
+        SitemapDocument::Index(entries)
+    } else {
+        SitemapDocument::UrlSet(entries)
+    })
+}
@@ -0,0 +1,201 @@
+use serde::{Deserialize, Serialize};
+
+use super::text::collapse_whitespace;
+
+// Characters that end a sentence when followed by whitespace
+const SENTENCE_TERMINATORS: [char; 4] = ['.', '!', '?', '…'];
+
+// Full-width terminators, which end a sentence without a space after them
+const FULL_WIDTH_TERMINATORS: [char; 3] = ['。', '！', '？'];
+
+// How snippets around keyword occurrences are cut
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnippetOptions {
+    #[serde(default = "default_window_chars")]
+    pub window_chars: usize, // Characters kept on each side of an occurrence
+    #[serde(default)]
+    pub max_per_keyword: Option<usize>, // Snippets reported per keyword and field (default: all)
+    #[serde(default)]
+    pub snap_to_sentence: bool, // Cut at the nearest sentence boundaries (within one more window) instead of mid-sentence
+}
+
+impl Default for SnippetOptions {
+    fn default() -> Self {
+        Self {
+            window_chars: default_window_chars(),
+            max_per_keyword: None,
+            snap_to_sentence: false,
+        }
+    }
+}
+
+fn default_window_chars() -> usize {
+    50
+}
+
+// Snippets around the given byte ranges, up to the configured maximum.
+// Nearby occurrences often produce the same snippet, which is kept once.
+pub fn collect_snippets(text: &str, ranges: &[(usize, usize)], options: &SnippetOptions) -> Vec<String> {
+    let max_snippets = options.max_per_keyword.unwrap_or(usize::MAX);
+    let mut snippets: Vec<String> = Vec::new();
+    for &(start, end) in ranges {
+        if snippets.len() >= max_snippets {
+            break;
+        }
+        let snippet = snippet_around(text, start, end, options);
+        if !snippets.contains(&snippet) {
+            snippets.push(snippet);
+        }
+    }
+    snippets
+}
+
+// Cut a snippet around the original byte range `start..end`
+pub fn snippet_around(text: &str, start: usize, end: usize, options: &SnippetOptions) -> String {
+    let mut snippet_start = move_back_chars(text, start, options.window_chars);
+    let mut snippet_end = move_forward_chars(text, end, options.window_chars);
+
+    if options.snap_to_sentence {
+        let search_start = move_back_chars(text, snippet_start, options.window_chars);
+        if let Some(sentence_start) = sentence_start_before(text, search_start, start) {
+            snippet_start = sentence_start;
+        }
+        let search_end = move_forward_chars(text, snippet_end, options.window_chars);
+        if let Some(sentence_end) = sentence_end_after(text, end, search_end) {
+            snippet_end = sentence_end;
+        }
+    }
+
+    collapse_whitespace(&text[snippet_start..snippet_end])
+}
+
+// Helper function to step back `count` chars from a byte offset
+fn move_back_chars(text: &str, offset: usize, count: usize) -> usize {
+    text[..offset].char_indices().rev().take(count).last().map_or(offset, |(index, _)| index)
+}
+
+// Helper function to step forward `count` chars from a byte offset
+fn move_forward_chars(text: &str, offset: usize, count: usize) -> usize {
+    text[offset..].char_indices().nth(count).map_or(text.len(), |(index, _)| offset + index)
+}
+
+// Helper function to find the start of the sentence containing `offset`,
+// looking no further back than `limit`
+fn sentence_start_before(text: &str, limit: usize, offset: usize) -> Option<usize> {
+    let mut next: Option<char> = None;
+    for (index, c) in text[limit..offset].char_indices().rev() {
+        if c == '\n' || ends_sentence(c, next.is_some_and(char::is_whitespace)) {
+            return Some(limit + index + c.len_utf8());
+        }
+        next = Some(c);
+    }
+    // The start of the text starts a sentence too
+    (limit == 0).then_some(0)
+}
+
+// Helper function to find the end of the sentence containing `offset`,
+// looking no further ahead than `limit`
+fn sentence_end_after(text: &str, offset: usize, limit: usize) -> Option<usize> {
+    let mut chars = text[offset..limit].char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        if c == '\n' {
+            return Some(offset + index);
+        }
+        if ends_sentence(c, chars.peek().is_none_or(|(_, next)| next.is_whitespace())) {
+            return Some(offset + index + c.len_utf8());
+        }
+    }
+    (limit == text.len()).then_some(limit)
+}
+
+// Helper function to decide whether a character ends a sentence
+fn ends_sentence(c: char, followed_by_space: bool) -> bool {
+    FULL_WIDTH_TERMINATORS.contains(&c) || (SENTENCE_TERMINATORS.contains(&c) && followed_by_space)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(window_chars: usize, snap_to_sentence: bool) -> SnippetOptions {
+        SnippetOptions {
+            window_chars,
+            max_per_keyword: None,
+            snap_to_sentence,
+        }
+    }
+
+    fn range_of(text: &str, needle: &str) -> (usize, usize) {
+        let start = text.find(needle).unwrap();
+        (start, start + needle.len())
+    }
+
+    #[test]
+    fn windows_count_characters_not_bytes() {
+        let text = "Ünïcödé çàfé — naïve façade €uro ★ stars";
+        let (start, end) = range_of(text, "naïve");
+        assert_eq!(snippet_around(text, start, end, &options(3, false)), "— naïve fa");
+
+        // Windows running past either end of the text stop at it
+        assert_eq!(snippet_around(text, start, end, &options(1000, false)), text);
+    }
+
+    #[test]
+    fn windows_around_multi_byte_scripts() {
+        let text = "東京都の天気は晴れです。大阪は雨でした。";
+        let (start, end) = range_of(text, "天気");
+        assert_eq!(snippet_around(text, start, end, &options(2, false)), "都の天気は晴");
+
+        let text = "Обзор рынка: банк вырос";
+        let (start, end) = range_of(text, "банк");
+        assert_eq!(snippet_around(text, start, end, &options(4, false)), "ка: банк выр");
+    }
+
+    #[test]
+    fn zero_window_keeps_only_the_occurrence() {
+        let text = "Der Bär läuft über die Straße";
+        let (start, end) = range_of(text, "über");
+        assert_eq!(snippet_around(text, start, end, &options(0, false)), "über");
+    }
+
+    #[test]
+    fn snap_to_sentence_at_the_start_and_end_of_the_text() {
+        let text = "Bank news first. Middle sentence here. Last words about the bank";
+        let snap = options(12, true);
+
+        // The first sentence starts at the start of the text
+        let (start, end) = range_of(text, "Bank");
+        assert_eq!(snippet_around(text, start, end, &snap), "Bank news first.");
+
+        // The last sentence has no terminator and ends with the text
+        let (start, end) = range_of(text, "bank");
+        assert_eq!(snippet_around(text, start, end, &snap), "Last words about the bank");
+
+        let (start, end) = range_of(text, "Middle");
+        assert_eq!(snippet_around(text, start, end, &snap), "Middle sentence here.");
+    }
+
+    #[test]
+    fn snap_to_sentence_with_multi_byte_terminators() {
+        // 。 ends a sentence without a space after it
+        let text = "天気は晴れ。東京の銀行は休み。大阪";
+        let (start, end) = range_of(text, "銀行");
+        assert_eq!(snippet_around(text, start, end, &options(2, true)), "東京の銀行は休み。");
+    }
+
+    #[test]
+    fn collect_snippets_dedups_and_caps() {
+        let text = "Café crème, café noir, café au lait";
+        let ranges: Vec<(usize, usize)> = text.match_indices("afé").map(|(start, m)| (start, start + m.len())).collect();
+        assert_eq!(ranges.len(), 3);
+
+        // A window covering the whole text gives the same snippet three times
+        assert_eq!(collect_snippets(text, &ranges, &options(100, false)), vec![text.to_string()]);
+
+        let capped = SnippetOptions {
+            max_per_keyword: Some(2),
+            ..options(3, false)
+        };
+        assert_eq!(collect_snippets(text, &ranges, &capped), vec!["Café cr", ", café no"]);
+    }
+}
@@ -0,0 +1,346 @@
+use scraper::{ElementRef, Html, Selector};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use url::Url;
+
+use super::text::collapse_whitespace;
+
+// Nested microdata items deeper than this are read as plain text
+const MAX_ITEM_DEPTH: usize = 16;
+
+// Article metadata from JSON-LD, schema.org microdata, OpenGraph and Twitter
+// Card tags. The typed fields take the first source that has a value, in that
+// order, with JSON-LD @graph nodes and @id references resolved; the raw
+// sources are kept for anything else a consumer needs.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct StructuredData {
+    pub item_type: Option<String>, // schema.org type or og:type, e.g. "NewsArticle"
+    pub headline: Option<String>,
+    pub description: Option<String>,
+    pub author: Option<String>, // Several authors are joined with ", "
+    pub image: Option<String>, // Absolute URL
+    pub section: Option<String>, // Article section, e.g. "Economy"
+    pub site_name: Option<String>,
+    pub json_ld: Vec<Value>, // Every JSON-LD block that parsed
+    pub microdata: Vec<MicrodataItem>, // Top-level itemscope elements
+    pub open_graph: BTreeMap<String, String>, // og:* and article:* properties
+    pub twitter_card: BTreeMap<String, String>, // twitter:* names
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MicrodataItem {
+    pub item_type: Option<String>,
+    pub properties: BTreeMap<String, Vec<MicrodataValue>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum MicrodataValue {
+    Text(String),
+    Item(MicrodataItem),
+}
+
+// Candidate values of one typed field, taken from each source
+#[derive(Default)]
+struct Summary {
+    item_type: Option<String>,
+    headline: Option<String>,
+    description: Option<String>,
+    author: Option<String>,
+    image: Option<String>,
+    section: Option<String>,
+    site_name: Option<String>,
+}
+
+impl StructuredData {
+    // Extract the structured data of a page, given its parsed JSON-LD blocks
+    // (see json_ld_blocks); `None` when it has none
+    pub fn extract(document: &Html, json_ld: Vec<Value>, page_url: &Url) -> Option<Self> {
+        let mut data = StructuredData {
+            json_ld,
+            microdata: microdata_items(document, page_url),
+            ..Default::default()
+        };
+        collect_meta_tags(document, &mut data);
+
+        if data.json_ld.is_empty() && data.microdata.is_empty() && data.open_graph.is_empty() && data.twitter_card.is_empty() {
+            return None;
+        }
+
+        let summaries = [
+            json_ld_summary(&data.json_ld),
+            microdata_summary(&data.microdata),
+            open_graph_summary(&data.open_graph),
+            twitter_summary(&data.twitter_card),
+        ];
+        let first = |field: fn(&Summary) -> &Option<String>| summaries.iter().find_map(|summary| field(summary).clone());
+        data.item_type = first(|summary| &summary.item_type);
+        data.headline = first(|summary| &summary.headline);
+        data.description = first(|summary| &summary.description);
+        data.author = first(|summary| &summary.author);
+        data.image = first(|summary| &summary.image).and_then(|image| page_url.join(&image).ok()).map(String::from);
+        data.section = first(|summary| &summary.section);
+        data.site_name = first(|summary| &summary.site_name);
+        Some(data)
+    }
+}
+
+// Parse every JSON-LD block of a page; invalid blocks are common and skipped
+pub fn json_ld_blocks(document: &Html) -> Vec<Value> {
+    let Ok(selector) = Selector::parse("script[type=\"application/ld+json\"]") else {
+        return Vec::new();
+    };
+    document
+        .select(&selector)
+        .filter_map(|script| serde_json::from_str(script.text().collect::<String>().trim()).ok())
+        .collect()
+}
+
+// Helper function to read og:*, article:* and twitter:* meta tags. OpenGraph
+// uses `property` and Twitter `name`, but sites mix them up.
+fn collect_meta_tags(document: &Html, data: &mut StructuredData) {
+    let Ok(selector) = Selector::parse("meta[content]") else {
+        return;
+    };
+    for meta in document.select(&selector) {
+        let element = meta.value();
+        let (Some(key), Some(content)) = (element.attr("property").or_else(|| element.attr("name")), element.attr("content")) else {
+            continue;
+        };
+        let key = key.trim().to_ascii_lowercase();
+        let content = content.trim();
+        if content.is_empty() {
+            continue;
+        }
+        let target = if key.starts_with("og:") || key.starts_with("article:") {
+            &mut data.open_graph
+        } else if key.starts_with("twitter:") {
+            &mut data.twitter_card
+        } else {
+            continue;
+        };
+        // Repeated tags (several og:image) keep the first one
+        target.entry(key).or_insert_with(|| content.to_string());
+    }
+}
+
+fn json_ld_summary(blocks: &[Value]) -> Summary {
+    // Flatten arrays and @graph into one list of nodes, indexed by @id
+    let mut nodes = Vec::new();
+    for block in blocks {
+        flatten_json_ld(block, &mut nodes);
+    }
+    let by_id: HashMap<&str, &Value> = nodes
+        .iter()
+        .filter_map(|node| Some((node.get("@id")?.as_str()?, *node)))
+        .collect();
+    let resolve = |value| resolve_reference(value, &by_id);
+
+    // The article is the node with a headline, or else the first node
+    let Some(main) = nodes.iter().find(|node| node.get("headline").is_some()).or(nodes.first()) else {
+        return Summary::default();
+    };
+    let text = |key: &str| main.get(key).and_then(first_text);
+    let names = |key: &str| {
+        let names: Vec<String> = as_list(main.get(key)?)
+            .into_iter()
+            .filter_map(|value| {
+                let value = resolve(value)?;
+                value.as_str().map(str::to_string).or_else(|| value.get("name").and_then(first_text))
+            })
+            .collect();
+        (!names.is_empty()).then(|| names.join(", "))
+    };
+    let image = main.get("image").and_then(|image| {
+        let image = resolve(as_list(image).into_iter().next()?)?;
+        image.as_str().or_else(|| image.get("url")?.as_str()).map(str::to_string)
+    });
+    let site_name = main.get("publisher").and_then(resolve).and_then(|publisher| publisher.get("name")).and_then(first_text);
+
+    Summary {
+        item_type: text("@type"),
+        headline: text("headline").or_else(|| text("name")),
+        description: text("description"),
+        author: names("author"),
+        image,
+        section: text("articleSection"),
+        site_name,
+    }
+}
+
+// Helper function to follow a bare {"@id": ...} reference to its node
+fn resolve_reference<'a>(value: &'a Value, by_id: &HashMap<&str, &'a Value>) -> Option<&'a Value> {
+    match value.get("@id").and_then(Value::as_str) {
+        Some(id) if value.as_object().is_some_and(|object| object.len() == 1) => by_id.get(id).copied(),
+        _ => Some(value),
+    }
+}
+
+// Helper function to collect the JSON-LD objects of a block, descending into
+// arrays and @graph but not into the properties of an object
+fn flatten_json_ld<'a>(value: &'a Value, nodes: &mut Vec<&'a Value>) {
+    match value {
+        Value::Array(items) => {
+            for item in items {
+                flatten_json_ld(item, nodes);
+            }
+        }
+        Value::Object(object) => match object.get("@graph") {
+            Some(graph) => flatten_json_ld(graph, nodes),
+            None => nodes.push(value),
+        },
+        _ => {}
+    }
+}
+
+fn as_list(value: &Value) -> Vec<&Value> {
+    match value {
+        Value::Array(items) => items.iter().collect(),
+        _ => vec![value],
+    }
+}
+
+// Helper function to read a string, or the first string of an array
+fn first_text(value: &Value) -> Option<String> {
+    as_list(value)
+        .into_iter()
+        .find_map(Value::as_str)
+        .map(str::trim)
+        .filter(|text| !text.is_empty())
+        .map(str::to_string)
+}
+
+// Helper function to find the top-level microdata items: itemscope elements
+// that are not the value of another item's property
+fn microdata_items(document: &Html, page_url: &Url) -> Vec<MicrodataItem> {
+    let Ok(selector) = Selector::parse("[itemscope]:not([itemprop])") else {
+        return Vec::new();
+    };
+    document.select(&selector).map(|element| microdata_item(element, page_url, 0)).collect()
+}
+
+fn microdata_item(element: ElementRef, page_url: &Url, depth: usize) -> MicrodataItem {
+    let mut item = MicrodataItem {
+        item_type: element.value().attr("itemtype").map(schema_type),
+        properties: BTreeMap::new(),
+    };
+    collect_properties(element, page_url, depth, &mut item.properties);
+    item
+}
+
+// Helper function to gather the itemprop descendants of an item, without
+// descending into nested items. The elements in between are walked with an
+// explicit stack, as pages can nest them deeply enough to overflow the call
+// stack.
+fn collect_properties(element: ElementRef, page_url: &Url, depth: usize, properties: &mut BTreeMap<String, Vec<MicrodataValue>>) {
+    // Children are pushed in reverse so they are visited in document order
+    let mut stack: Vec<ElementRef> = element.children().filter_map(ElementRef::wrap).rev().collect();
+    while let Some(child) = stack.pop() {
+        let child_element = child.value();
+        let is_scope = child_element.attr("itemscope").is_some();
+        // One element may hold several space-separated properties
+        for name in child_element.attr("itemprop").into_iter().flat_map(str::split_whitespace) {
+            let value = if is_scope && depth < MAX_ITEM_DEPTH {
+                MicrodataValue::Item(microdata_item(child, page_url, depth + 1))
+            } else {
+                MicrodataValue::Text(property_value(child, page_url))
+            };
+            properties.entry(name.to_string()).or_default().push(value);
+        }
+        if !is_scope {
+            stack.extend(child.children().filter_map(ElementRef::wrap).rev());
+        }
+    }
+}
+
+// Helper function to read a property value the way the microdata spec does
+fn property_value(element: ElementRef, page_url: &Url) -> String {
+    let value = element.value();
+    let url_attr = |name: &str| {
+        value
+            .attr(name)
+            .map(|url| page_url.join(url).map(String::from).unwrap_or_else(|_| url.to_string()))
+    };
+    let attribute = match value.name() {
+        "meta" => value.attr("content").map(str::to_string),
+        "img" | "audio" | "video" | "source" | "iframe" | "embed" | "track" => url_attr("src"),
+        "a" | "area" | "link" => url_attr("href"),
+        "object" => url_attr("data"),
+        "time" => value.attr("datetime").map(str::to_string),
+        "data" | "meter" => value.attr("value").map(str::to_string),
+        _ => None,
+    };
+    attribute.unwrap_or_else(|| collapse_whitespace(&element.text().collect::<Vec<_>>().join(" ")))
+}
+
+// Helper function to shorten "https://schema.org/NewsArticle" to "NewsArticle"
+fn schema_type(item_type: &str) -> String {
+    let first = item_type.split_whitespace().next().unwrap_or("");
+    first.rsplit('/').next().unwrap_or(first).to_string()
+}
+
+fn microdata_summary(items: &[MicrodataItem]) -> Summary {
+    let Some(main) = items.iter().find(|item| item.properties.contains_key("headline")).or(items.first()) else {
+        return Summary::default();
+    };
+    let text = |key: &str| {
+        main.properties.get(key)?.iter().find_map(|value| match value {
+            MicrodataValue::Text(text) if !text.is_empty() => Some(text.clone()),
+            _ => None,
+        })
+    };
+    // Nested items (a Person author, an ImageObject) are read through their name or url
+    let nested = |key: &str, inner: &str| -> Vec<String> {
+        main.properties
+            .get(key)
+            .into_iter()
+            .flatten()
+            .filter_map(|value| match value {
+                MicrodataValue::Text(text) if !text.is_empty() => Some(text.clone()),
+                MicrodataValue::Item(item) => item.properties.get(inner)?.iter().find_map(|value| match value {
+                    MicrodataValue::Text(text) if !text.is_empty() => Some(text.clone()),
+                    _ => None,
+                }),
+                _ => None,
+            })
+            .collect()
+    };
+    let authors = nested("author", "name");
+
+    Summary {
+        item_type: main.item_type.clone(),
+        headline: text("headline").or_else(|| text("name")),
+        description: text("description"),
+        author: (!authors.is_empty()).then(|| authors.join(", ")),
+        image: nested("image", "url").into_iter().next(),
+        section: text("articleSection"),
+        site_name: nested("publisher", "name").into_iter().next(),
+    }
+}
+
+fn open_graph_summary(tags: &BTreeMap<String, String>) -> Summary {
+    let tag = |key: &str| tags.get(key).cloned();
+    Summary {
+        item_type: tag("og:type"),
+        headline: tag("og:title"),
+        description: tag("og:description"),
+        author: tag("article:author"),
+        image: tag("og:image").or_else(|| tag("og:image:url")),
+        section: tag("article:section"),
+        site_name: tag("og:site_name"),
+    }
+}
+
+fn twitter_summary(tags: &BTreeMap<String, String>) -> Summary {
+    let tag = |key: &str| tags.get(key).cloned();
+    Summary {
+        item_type: None,
+        headline: tag("twitter:title"),
+        description: tag("twitter:description"),
+        author: tag("twitter:creator"),
+        image: tag("twitter:image").or_else(|| tag("twitter:image:src")),
+        section: None,
+        site_name: None, // twitter:site is an account handle
+    }
+}
@@ -0,0 +1,381 @@
+use scraper::{ElementRef, Html, Selector};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+
+use super::text::collapse_whitespace;
+
+// Upper bound for colspan/rowspan, as in browsers
+const MAX_SPAN: usize = 1000;
+
+// Upper bounds for a table's grid, spanned cells counted once per cell they
+// cover; larger tables are skipped
+const MAX_GRID_CELLS: usize = 50_000;
+const MAX_GRID_TEXT_BYTES: usize = 5 * 1024 * 1024;
+
+// A data table of a page, one JSON object per body row keyed by column name
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DataTable {
+    pub caption: Option<String>,
+    pub columns: Vec<String>, // Column names in table order; stacked header rows are joined with " / "
+    pub rows: Vec<BTreeMap<String, String>>,
+    pub csv: Option<String>, // The same table as CSV, with tables_csv
+}
+
+// Cell text of a table laid out on a grid, with colspans and rowspans
+// repeated into every cell they cover
+pub struct TableGrid {
+    pub header_rows: usize, // Leading rows that hold column headers
+    pub cells: Vec<Vec<String>>,
+}
+
+// Cells and text laid out so far, checked against the grid limits
+#[derive(Default)]
+struct GridBudget {
+    cells: usize,
+    text_bytes: usize,
+}
+
+impl GridBudget {
+    fn spend(&mut self, text: &str) {
+        self.cells += 1;
+        self.text_bytes += text.len();
+    }
+
+    fn exceeded(&self) -> bool {
+        self.cells > MAX_GRID_CELLS || self.text_bytes > MAX_GRID_TEXT_BYTES
+    }
+}
+
+// Extract the data tables of a page; layout tables and tables over the grid
+// limits are skipped
+pub fn extract_tables(document: &Html, with_csv: bool) -> Vec<DataTable> {
+    let Ok(selector) = Selector::parse("table") else {
+        return Vec::new();
+    };
+    document
+        .select(&selector)
+        .filter_map(|table| {
+            let grid = table_grid(table)?;
+            if !is_data_table(table, &grid) {
+                return None;
+            }
+            let columns = column_names(&grid);
+            let body = &grid.cells[grid.header_rows..];
+            if columns.len() < 2 || body.is_empty() {
+                return None;
+            }
+            let rows = body
+                .iter()
+                .map(|row| columns.iter().cloned().zip(row.iter().cloned()).collect())
+                .collect();
+            Some(DataTable {
+                caption: child_text(table, "caption"),
+                csv: with_csv.then(|| to_csv(&columns, body)),
+                columns,
+                rows,
+            })
+        })
+        .collect()
+}
+
+// Helper function to tell data tables from layout tables, after Readability:
+// explicit markers decide first, then nesting, then the size of its grid
+fn is_data_table(table: ElementRef, grid: &TableGrid) -> bool {
+    let element = table.value();
+    if element.attr("role") == Some("presentation") || element.attr("datatable") == Some("0") {
+        return false;
+    }
+    if element.attr("summary").is_some() || child_text(table, "caption").is_some() {
+        return true;
+    }
+    let has_table_markup = Selector::parse("col, colgroup, thead, tfoot, th")
+        .map(|selector| table.select(&selector).next().is_some())
+        .unwrap_or(false);
+    if has_table_markup {
+        return true;
+    }
+    // select() includes the table itself
+    let has_nested_table = Selector::parse("table")
+        .map(|selector| table.select(&selector).any(|nested| nested.id() != table.id()))
+        .unwrap_or(false);
+    if has_nested_table {
+        return false;
+    }
+    let rows = grid.cells.len();
+    let columns = grid.cells.first().map_or(0, Vec::len);
+    rows >= 10 || columns > 4 || rows * columns > 10
+}
+
+// Lay out the rows of a table (not of tables nested in it) on a grid; `None`
+// when the grid would exceed MAX_GRID_CELLS or MAX_GRID_TEXT_BYTES
+pub fn table_grid(table: ElementRef) -> Option<TableGrid> {
+    let mut rows: Vec<(ElementRef, bool)> = Vec::new(); // (row, in <thead>)
+    for child in table.children().filter_map(ElementRef::wrap) {
+        match child.value().name() {
+            "tr" => rows.push((child, false)),
+            section @ ("thead" | "tbody" | "tfoot") => rows.extend(
+                child
+                    .children()
+                    .filter_map(ElementRef::wrap)
+                    .filter(|row| row.value().name() == "tr")
+                    .map(|row| (row, section == "thead")),
+            ),
+            _ => {}
+        }
+    }
+
+    let mut cells: Vec<Vec<String>> = Vec::new();
+    // Per column: rows a rowspan still covers, and its text
+    let mut carried: Vec<Option<(usize, String)>> = Vec::new();
+    let mut header_rows = 0;
+    let mut in_header = true;
+    let mut budget = GridBudget::default();
+
+    for (row, in_head) in rows {
+        let row_cells: Vec<ElementRef> = row
+            .children()
+            .filter_map(ElementRef::wrap)
+            .filter(|cell| matches!(cell.value().name(), "td" | "th"))
+            .collect();
+        if in_header && (in_head || (!row_cells.is_empty() && row_cells.iter().all(|cell| cell.value().name() == "th"))) {
+            header_rows += 1;
+        } else {
+            in_header = false;
+        }
+
+        let mut line = Vec::new();
+        let mut column = 0;
+        for cell in row_cells {
+            while take_carried(&mut carried, &mut line, column, &mut budget) {
+                column += 1;
+            }
+            let text = cell_text(cell);
+            let colspan = span(cell, "colspan");
+            let rowspan = span(cell, "rowspan");
+            for _ in 0..colspan {
+                budget.spend(&text);
+                if budget.exceeded() {
+                    return None;
+                }
+                if rowspan > 1 {
+                    if carried.len() <= column {
+                        carried.resize(column + 1, None);
+                    }
+                    carried[column] = Some((rowspan - 1, text.clone()));
+                }
+                line.push(text.clone());
+                column += 1;
+            }
+        }
+        // Rowspans reaching past the row's own cells
+        for trailing in column..carried.len() {
+            if carried[trailing].is_some() {
+                line.resize(trailing, String::new());
+                take_carried(&mut carried, &mut line, trailing, &mut budget);
+            }
+        }
+        if budget.exceeded() {
+            return None;
+        }
+        cells.push(line);
+    }
+
+    // Drop rows without any text and square the grid
+    let mut kept = Vec::with_capacity(cells.len());
+    let mut kept_header_rows = 0;
+    for (index, line) in cells.into_iter().enumerate() {
+        if line.iter().all(String::is_empty) {
+            continue;
+        }
+        if index < header_rows {
+            kept_header_rows += 1;
+        }
+        kept.push(line);
+    }
+    let width = kept.iter().map(Vec::len).max().unwrap_or(0);
+    if width.saturating_mul(kept.len()) > MAX_GRID_CELLS {
+        return None;
+    }
+    for line in &mut kept {
+        line.resize(width, String::new());
+    }
+    Some(TableGrid {
+        header_rows: kept_header_rows,
+        cells: kept,
+    })
+}
+
+// Helper function to fill a column still covered by a rowspan from an
+// earlier row; returns false when the column is free
+fn take_carried(carried: &mut [Option<(usize, String)>], line: &mut Vec<String>, column: usize, budget: &mut GridBudget) -> bool {
+    let Some(slot) = carried.get_mut(column) else {
+        return false;
+    };
+    let Some((remaining, text)) = slot.as_mut() else {
+        return false;
+    };
+    budget.spend(text);
+    line.push(text.clone());
+    *remaining -= 1;
+    if *remaining == 0 {
+        *slot = None;
+    }
+    true
+}
+
+// Helper function to name the columns from the header rows: stacked headers
+// are joined ("2023 / Q1"), empty ones become "column_n" and repeated names
+// get a "_2", "_3", ... suffix
+pub fn column_names(grid: &TableGrid) -> Vec<String> {
+    let width = grid.cells.first().map_or(0, Vec::len);
+    let mut seen = HashSet::new();
+    (0..width)
+        .map(|column| {
+            let mut parts: Vec<&str> = Vec::new();
+            for row in &grid.cells[..grid.header_rows] {
+                let part = row[column].as_str();
+                if !part.is_empty() && parts.last() != Some(&part) {
+                    parts.push(part);
+                }
+            }
+            let name = if parts.is_empty() { format!("column_{}", column + 1) } else { parts.join(" / ") };
+            let mut unique = name.clone();
+            let mut suffix = 2;
+            while !seen.insert(unique.clone()) {
+                unique = format!("{}_{}", name, suffix);
+                suffix += 1;
+            }
+            unique
+        })
+        .collect()
+}
+
+// Helper function to write a header row and body rows as RFC 4180 CSV
+fn to_csv(columns: &[String], rows: &[Vec<String>]) -> String {
+    let line = |cells: &[String]| {
+        cells
+            .iter()
+            .map(|cell| {
+                if cell.contains([',', '"', '\n', '\r']) {
+                    format!("\"{}\"", cell.replace('"', "\"\""))
+                } else {
+                    cell.clone()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(",")
+    };
+    let mut csv = String::new();
+    for cells in std::iter::once(columns).chain(rows.iter().map(Vec::as_slice)) {
+        csv.push_str(&line(cells));
+        csv.push_str("\r\n");
+    }
+    csv
+}
+
+fn span(cell: ElementRef, attribute: &str) -> usize {
+    cell.value()
+        .attr(attribute)
+        .and_then(|value| value.trim().parse::<usize>().ok())
+        .unwrap_or(1)
+        .clamp(1, MAX_SPAN)
+}
+
+fn cell_text(cell: ElementRef) -> String {
+    collapse_whitespace(&cell.text().collect::<Vec<_>>().join(" "))
+}
+
+// Helper function to read the text of a direct child element such as <caption>
+fn child_text(element: ElementRef, name: &str) -> Option<String> {
+    element
+        .children()
+        .filter_map(ElementRef::wrap)
+        .find(|child| child.value().name() == name)
+        .map(cell_text)
+        .filter(|text| !text.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(html: &str) -> Option<TableGrid> {
+        let document = Html::parse_fragment(html);
+        let selector = Selector::parse("table").unwrap();
+        table_grid(document.select(&selector).next().unwrap())
+    }
+
+    #[test]
+    fn colspan_and_rowspan() {
+        let grid = grid(
+            "<table>
+               <tr><th rowspan=2>Region</th><th colspan=2>2023</th></tr>
+               <tr><th>Q1</th><th>Q2</th></tr>
+               <tr><td rowspan=2>North</td><td>1</td><td>2</td></tr>
+               <tr><td>3</td><td>4</td></tr>
+             </table>",
+        )
+        .unwrap();
+        assert_eq!(grid.header_rows, 2);
+        assert_eq!(
+            grid.cells,
+            [
+                ["Region", "2023", "2023"],
+                ["Region", "Q1", "Q2"],
+                ["North", "1", "2"],
+                ["North", "3", "4"],
+            ]
+        );
+        assert_eq!(column_names(&grid), ["Region", "2023 / Q1", "2023 / Q2"]);
+    }
+
+    #[test]
+    fn rowspan_past_the_row_end() {
+        let grid = grid(
+            "<table>
+               <tr><td>a</td><td rowspan=3>b</td></tr>
+               <tr><td>c</td></tr>
+               <tr><td>d</td><td>e</td></tr>
+             </table>",
+        )
+        .unwrap();
+        assert_eq!(grid.header_rows, 0);
+        assert_eq!(grid.cells, [["a", "b", ""], ["c", "b", ""], ["d", "b", "e"]]);
+    }
+
+    #[test]
+    fn ragged_rows_and_sections() {
+        let grid = grid(
+            "<table>
+               <thead><tr><td>Name</td><td>Name</td></tr></thead>
+               <tbody><tr><td>x</td></tr><tr><td></td><td></td></tr><tr><td>y</td><td>z</td></tr></tbody>
+             </table>",
+        )
+        .unwrap();
+        assert_eq!(grid.header_rows, 1);
+        assert_eq!(grid.cells, [["Name", "Name"], ["x", ""], ["y", "z"]]);
+        assert_eq!(column_names(&grid), ["Name", "Name_2"]);
+    }
+
+    #[test]
+    fn spans_are_clamped_and_grids_capped() {
+        let grid = grid("<table><tr><td colspan=0>a</td><td colspan=x>b</td><td colspan=3>c</td></tr></table>").unwrap();
+        assert_eq!(grid.cells, [["a", "b", "c", "c", "c"]]);
+        let huge = format!("<table>{}</table>", "<tr><td colspan=1000 rowspan=1000>x</td></tr>".repeat(100));
+        assert!(self::grid(&huge).is_none());
+    }
+
+    #[test]
+    fn data_and_layout_tables() {
+        let document = Html::parse_document(
+            "<table role=presentation><tr><th>a</th><th>b</th></tr><tr><td>1</td><td>2</td></tr></table>
+             <table><tr><td>nav</td><td>main</td></tr></table>
+             <table><caption>Prices</caption><tr><th>Item</th><th>Price</th></tr><tr><td>Tea</td><td>1,50</td></tr></table>",
+        );
+        let tables = extract_tables(&document, true);
+        assert_eq!(tables.len(), 1);
+        assert_eq!(tables[0].caption.as_deref(), Some("Prices"));
+        assert_eq!(tables[0].rows[0]["Price"], "1,50");
+        assert_eq!(tables[0].csv.as_deref(), Some("Item,Price\r\nTea,\"1,50\"\r\n"));
+    }
+}
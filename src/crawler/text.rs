@@ -0,0 +1,169 @@
+use ego_tree::iter::Edge;
+use ego_tree::NodeId;
+use scraper::{ElementRef, Html, Selector};
+use serde::{Deserialize, Serialize};
+
+// Elements whose content is never rendered as page text
+pub const HIDDEN_ELEMENTS: [&str; 12] = [
+    "head", "script", "style", "noscript", "template", "svg", "math", "iframe", "object", "canvas", "select", "button",
+];
+
+// Elements that start a new line in the extracted text
+pub const BLOCK_ELEMENTS: [&str; 34] = [
+    "address", "article", "aside", "blockquote", "br", "dd", "details", "div", "dl", "dt", "fieldset", "figcaption",
+    "figure", "footer", "form", "h1", "h2", "h3", "h4", "h5", "h6", "header", "hr", "li", "main", "nav", "ol", "p",
+    "pre", "section", "summary", "table", "tr", "ul",
+];
+
+// Part of a page that keywords are matched against
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchField {
+    Body,            // Visible text of the page
+    Title,           // <title>
+    MetaDescription, // <meta name="description">
+    AltText,         // alt attributes of images
+}
+
+// Where the body text of a page comes from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TextSource {
+    #[default]
+    FullPage,    // Everything visible on the page, including menus and footers
+    MainContent, // The main content block only, without navigation and other boilerplate
+}
+
+// Text of a page split into the fields keywords can be matched against
+#[derive(Debug, Default)]
+pub struct PageText {
+    pub title: Option<String>,
+    pub meta_description: Option<String>,
+    pub body: String,     // One line per block element, whitespace collapsed
+    pub alt_text: String, // One line per image
+}
+
+impl PageText {
+    // Title and meta description come from `document`; the body and alt
+    // text come from `main_content` when given (see readability.rs)
+    pub fn extract(document: &Html, main_content: Option<&Html>) -> Self {
+        let mut page_text = PageText {
+            title: select_text(document, "title"),
+            meta_description: meta_description(document),
+            ..Default::default()
+        };
+
+        let mut lines = Vec::new();
+        let mut line = String::new();
+        let body_document = main_content.unwrap_or(document);
+        let root = Selector::parse("body")
+            .ok()
+            .and_then(|selector| body_document.select(&selector).next())
+            .unwrap_or_else(|| body_document.root_element());
+        collect_visible_text(root, &mut lines, &mut line);
+        push_line(&mut lines, &mut line);
+        page_text.body = lines.join("\n");
+
+        if let Ok(img_selector) = Selector::parse("img[alt]") {
+            page_text.alt_text = body_document
+                .select(&img_selector)
+                .filter_map(|img| img.value().attr("alt"))
+                .map(collapse_whitespace)
+                .filter(|alt| !alt.is_empty())
+                .collect::<Vec<_>>()
+                .join("\n");
+        }
+
+        page_text
+    }
+
+    pub fn field(&self, field: MatchField) -> &str {
+        match field {
+            MatchField::Body => &self.body,
+            MatchField::Title => self.title.as_deref().unwrap_or(""),
+            MatchField::MetaDescription => self.meta_description.as_deref().unwrap_or(""),
+            MatchField::AltText => &self.alt_text,
+        }
+    }
+}
+
+// Helper function to walk the element tree, skipping content that is never
+// shown. The walk uses the tree's own traversal rather than recursion, as
+// pages can nest elements deeply enough to overflow the stack.
+fn collect_visible_text(root: ElementRef, lines: &mut Vec<String>, line: &mut String) {
+    // Hidden element whose subtree is being skipped
+    let mut hidden: Option<NodeId> = None;
+
+    for edge in root.traverse() {
+        match edge {
+            Edge::Open(node) => {
+                if hidden.is_some() {
+                    continue;
+                }
+                if let Some(element) = node.value().as_element() {
+                    let name = element.name();
+                    if HIDDEN_ELEMENTS.contains(&name) || element.attr("hidden").is_some() {
+                        hidden = Some(node.id());
+                        continue;
+                    }
+                    // Table cells stay on their row's line
+                    if matches!(name, "td" | "th") && !line.is_empty() {
+                        line.push(' ');
+                    }
+                    if BLOCK_ELEMENTS.contains(&name) {
+                        push_line(lines, line);
+                    }
+                } else if let Some(text) = node.value().as_text() {
+                    // Collapse whitespace but keep word boundaries between text
+                    // nodes, so "<b>R</b>ust" stays one word
+                    if text.starts_with(char::is_whitespace) && !line.is_empty() && !line.ends_with(' ') {
+                        line.push(' ');
+                    }
+                    line.push_str(&collapse_whitespace(text));
+                    if text.ends_with(char::is_whitespace) && !line.is_empty() && !line.ends_with(' ') {
+                        line.push(' ');
+                    }
+                }
+            }
+            Edge::Close(node) => {
+                if hidden == Some(node.id()) {
+                    hidden = None;
+                } else if hidden.is_none() && node.value().as_element().is_some_and(|element| BLOCK_ELEMENTS.contains(&element.name())) {
+                    push_line(lines, line);
+                }
+            }
+        }
+    }
+}
+
+// Helper function to finish the current line of text
+fn push_line(lines: &mut Vec<String>, line: &mut String) {
+    let finished = line.trim();
+    if !finished.is_empty() {
+        lines.push(finished.to_string());
+    }
+    line.clear();
+}
+
+// Helper function to read the text of the first element matching a selector
+fn select_text(document: &Html, selector_str: &str) -> Option<String> {
+    let selector = Selector::parse(selector_str).ok()?;
+    let element = document.select(&selector).next()?;
+    Some(collapse_whitespace(&element.text().collect::<String>())).filter(|text| !text.is_empty())
+}
+
+// Helper function to read <meta name="description">, whose name is case-insensitive
+fn meta_description(document: &Html) -> Option<String> {
+    let selector = Selector::parse("meta[name][content]").ok()?;
+    document
+        .select(&selector)
+        .find(|meta| meta.value().attr("name").is_some_and(|name| name.eq_ignore_ascii_case("description")))
+        .and_then(|meta| meta.value().attr("content"))
+        .map(collapse_whitespace)
+        .filter(|description| !description.is_empty())
+}
+
+// Helper function to collapse runs of whitespace into single spaces and trim the ends
+pub(super) fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
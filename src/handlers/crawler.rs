@@ -29,6 +29,17 @@ pub async fn crawl_website(
                 CrawlerError::SelectorError(e) => (StatusCode::BAD_REQUEST, format!("Selector error: {}", e)),
                 CrawlerError::TimeoutError => (StatusCode::OK, "Crawling exceeded the time limit".to_string()),
                 CrawlerError::DateParsingError(e) => (StatusCode::BAD_REQUEST, format!("Date parsing error: {}", e)),
+                CrawlerError::HttpStatus { status, url } => (StatusCode::BAD_GATEWAY, format!("HTTP {} returned by {}", status, url)),
+                CrawlerError::TooManyRedirects(e) => (StatusCode::BAD_GATEWAY, format!("Too many redirects: {}", e)),
+                CrawlerError::RobotsDisallowed(e) => (StatusCode::FORBIDDEN, format!("Disallowed by robots.txt: {}", e)),
+                CrawlerError::RobotsUnavailable(e) => (StatusCode::BAD_GATEWAY, format!("robots.txt unreachable: {}", e)),
+                CrawlerError::RedirectSkipped { url, .. } => (StatusCode::BAD_GATEWAY, format!("Redirect target skipped: {}", url)),
+                CrawlerError::FeedError(e) => (StatusCode::BAD_REQUEST, format!("Feed error: {}", e)),
+                CrawlerError::InvalidPattern(e) => (StatusCode::BAD_REQUEST, format!("Invalid URL pattern: {}", e)),
+                CrawlerError::QueryError(e) => (StatusCode::BAD_REQUEST, format!("Invalid query: {}", e)),
+                CrawlerError::KeywordError(e) => (StatusCode::BAD_REQUEST, format!("Invalid keyword: {}", e)),
+                CrawlerError::PaginationError(e) => (StatusCode::BAD_REQUEST, format!("Invalid pagination: {}", e)),
+                CrawlerError::ScoringError(e) => (StatusCode::BAD_REQUEST, format!("Invalid scoring options: {}", e)),
                 CrawlerError::Other(e) => (StatusCode::BAD_REQUEST, format!("Other error: {}", e)),
             };
             
@@ -16,7 +16,7 @@ pub async fn produce_json(
     key: Option<&str>,
     payload: &Value,
 ) -> Result<(), KafkaError> {
-    let payload_str = serde_json::to_string(payload).map_err(|_| KafkaError::MessageProduction("serialize".into()))?;
+    let payload_str = serde_json::to_string(payload).map_err(|_| KafkaError::MessageProduction(rdkafka::types::RDKafkaErrorCode::InvalidMessage))?;
     let record = FutureRecord::to(topic)
         .payload(&payload_str)
         .key(key.unwrap_or(""));